@@ -1,10 +1,15 @@
+pub use zoid_span::Span;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[rustfmt::skip]
 pub enum Type {
     I8, I16, I32, I64, I128,
     U8, U16, U32, U64, U128,
     F32, F64,
+    Bool,
     Void,
+    /// `*T`.
+    Pointer(Box<Type>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -17,6 +22,30 @@ pub enum TopLevelExpression<'source> {
         parameters: Vec<Parameter<'source>>,
         return_type: Option<Type>,
         body: Vec<Statement<'source>>,
+        span: Span,
+    },
+    /// `extern "C" fn name(a: T, ...): R;`, a function defined outside the
+    /// program, such as in the C library.
+    ExternFunction {
+        /// The calling convention, such as `C`.
+        abi: &'source str,
+        name: &'source str,
+        parameters: Vec<Parameter<'source>>,
+        return_type: Option<Type>,
+        /// Whether the parameters end with `...`, taking any number of
+        /// further arguments as C does.
+        variadic: bool,
+        span: Span,
+    },
+    /// `cimport "header.h";`. The driver imports the header's declarations
+    /// as a file of their own, so this is otherwise ignored.
+    CImport { header: &'source str, span: Span },
+    /// `const NAME: T = value;`, evaluated at compile time.
+    Constant {
+        name: &'source str,
+        ty: Type,
+        value: Expression<'source>,
+        span: Span,
     },
 }
 
@@ -24,6 +53,7 @@ pub enum TopLevelExpression<'source> {
 pub struct Parameter<'source> {
     pub name: &'source str,
     pub ty: Type,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -32,25 +62,89 @@ pub enum Statement<'source> {
         name: &'source str,
         ty: Option<Type>,
         value: Expression<'source>,
+        span: Span,
     },
-    Return(Option<Expression<'source>>),
+    Return(Option<Expression<'source>>, Span),
+    /// An expression evaluated for its side effects, such as a call.
+    Expression(Expression<'source>, Span),
+    /// `target = value`, or a compound assignment such as `target += value`
+    /// when `op` is set.
+    Assignment {
+        target: Expression<'source>,
+        op: Option<BinaryOperator>,
+        value: Expression<'source>,
+        span: Span,
+    },
+    /// An `if` statement. An `else if` is an `else` branch containing only
+    /// another `If`.
+    If {
+        condition: Expression<'source>,
+        then_branch: Vec<Statement<'source>>,
+        else_branch: Option<Vec<Statement<'source>>>,
+        span: Span,
+    },
+    While {
+        condition: Expression<'source>,
+        body: Vec<Statement<'source>>,
+        span: Span,
+    },
+    Break(Span),
+    Continue(Span),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expression<'source> {
-    Literal(Literal<'source>),
-    Variable(&'source str),
+    Literal(Literal<'source>, Span),
+    Variable(&'source str, Span),
     BinaryOperation {
         lhs: Box<Expression<'source>>,
         op: BinaryOperator,
         rhs: Box<Expression<'source>>,
+        span: Span,
+    },
+    UnaryOperation {
+        op: UnaryOperator,
+        operand: Box<Expression<'source>>,
+        span: Span,
+    },
+    Call {
+        callee: &'source str,
+        arguments: Vec<Expression<'source>>,
+        span: Span,
+    },
+    /// `value as ty`, converting between numeric types.
+    Cast {
+        value: Box<Expression<'source>>,
+        ty: Type,
+        span: Span,
+    },
+    /// `operand.&`, the address of a variable or dereferenced pointer.
+    AddressOf {
+        operand: Box<Expression<'source>>,
+        span: Span,
+    },
+    /// `operand.*`, the value a pointer points to.
+    Dereference {
+        operand: Box<Expression<'source>>,
+        span: Span,
+    },
+    /// `base[index]`, the same as `(base + index).*`.
+    Index {
+        base: Box<Expression<'source>>,
+        index: Box<Expression<'source>>,
+        span: Span,
     },
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Literal<'source> {
-    Integer(&'source str),
+    /// The value of an integer literal, along with the type given by its
+    /// suffix (as in `255u8`), if any.
+    Integer(u128, Option<Type>),
     Float(&'source str),
+    Bool(bool),
+    /// `null`, a pointer to nothing.
+    Null,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -60,4 +154,102 @@ pub enum BinaryOperator {
     Mul,
     Div,
     Rem,
+    /// `+%`, which wraps around on overflow. Only for integers, as are the
+    /// other wrapping and saturating operators.
+    WrappingAdd,
+    /// `-%`.
+    WrappingSub,
+    /// `*%`.
+    WrappingMul,
+    /// `+|`, which gives the type's minimum or maximum on overflow.
+    SaturatingAdd,
+    /// `-|`.
+    SaturatingSub,
+    /// `*|`.
+    SaturatingMul,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    /// `<<`. The right operand may be any integer type.
+    Shl,
+    /// `>>`, which is arithmetic for signed types and logical for unsigned
+    /// ones. The right operand may be any integer type.
+    Shr,
+
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+
+    /// `&&` or `and`, which only evaluates its right operand if the left one
+    /// is `true`.
+    And,
+    /// `||` or `or`, which only evaluates its right operand if the left one
+    /// is `false`.
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    /// `-`, for signed integers and floats.
+    Neg,
+    /// `!` or `not`, for `bool`.
+    Not,
+    /// `~`, for integers.
+    BitNot,
+}
+
+impl<'source> TopLevelExpression<'source> {
+    pub fn span(&self) -> Span {
+        match self {
+            TopLevelExpression::Function { span, .. } => *span,
+            TopLevelExpression::ExternFunction { span, .. } => *span,
+            TopLevelExpression::CImport { span, .. } => *span,
+            TopLevelExpression::Constant { span, .. } => *span,
+        }
+    }
+
+    /// The name the item declares, or `None` for a `cimport`.
+    pub fn name(&self) -> Option<&'source str> {
+        match self {
+            TopLevelExpression::Function { name, .. } => Some(name),
+            TopLevelExpression::ExternFunction { name, .. } => Some(name),
+            TopLevelExpression::CImport { .. } => None,
+            TopLevelExpression::Constant { name, .. } => Some(name),
+        }
+    }
+}
+
+impl Statement<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::VariableDeclaration { span, .. } => *span,
+            Statement::Return(_, span) => *span,
+            Statement::Expression(_, span) => *span,
+            Statement::Assignment { span, .. } => *span,
+            Statement::If { span, .. } => *span,
+            Statement::While { span, .. } => *span,
+            Statement::Break(span) => *span,
+            Statement::Continue(span) => *span,
+        }
+    }
+}
+
+impl Expression<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Literal(_, span) => *span,
+            Expression::Variable(_, span) => *span,
+            Expression::BinaryOperation { span, .. } => *span,
+            Expression::UnaryOperation { span, .. } => *span,
+            Expression::Call { span, .. } => *span,
+            Expression::Cast { span, .. } => *span,
+            Expression::AddressOf { span, .. } => *span,
+            Expression::Dereference { span, .. } => *span,
+            Expression::Index { span, .. } => *span,
+        }
+    }
 }
@@ -0,0 +1,35 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    io,
+};
+
+/// Why a header could not be imported at all. Declarations that cannot be
+/// translated are not errors, and are listed in `CImport::skipped` instead.
+#[derive(Debug)]
+pub enum CImportError {
+    /// The C compiler could not be run.
+    Compiler { compiler: String, error: io::Error },
+    /// The C compiler failed to preprocess the header, usually because it
+    /// does not exist. Holds the compiler's error output.
+    Preprocess { header: String, message: String },
+}
+
+impl Display for CImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CImportError::Compiler { compiler, error } => {
+                write!(f, "could not run the C compiler `{}`: {}", compiler, error)
+            }
+            CImportError::Preprocess { header, message } => {
+                write!(
+                    f,
+                    "could not preprocess `{}`: {}",
+                    header,
+                    message.trim_end()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CImportError {}
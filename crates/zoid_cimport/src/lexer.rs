@@ -0,0 +1,149 @@
+/// A token of preprocessed C. Only as much of C is distinguished as the
+/// declaration parser needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CToken {
+    Identifier(String),
+    /// An integer or floating point number, with any suffix.
+    Number(String),
+    /// A string or character literal, including its quotes.
+    Literal(String),
+    Punct(&'static str),
+}
+
+impl CToken {
+    pub fn is_punct(&self, punct: &str) -> bool {
+        matches!(self, CToken::Punct(p) if *p == punct)
+    }
+}
+
+/// Longest first, so `...` is not lexed as three `.`s.
+const PUNCTUATION: &[&str] = &[
+    "...", "<<=", ">>=", "->", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "*=",
+    "/=", "%=", "+=", "-=", "&=", "^=", "|=", "##", "[", "]", "(", ")", "{", "}", ".", "&", "*",
+    "+", "-", "~", "!", "/", "%", "<", ">", "^", "|", "?", ":", ";", "=", ",", "#",
+];
+
+/// Splits the output of the preprocessor into tokens. Lines starting with
+/// `#`, such as line markers and pragmas, are skipped. Characters that are
+/// not part of any token are dropped, as they can only be inside
+/// declarations that will fail to parse anyway.
+pub fn tokenize(source: &str) -> Vec<CToken> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line_start = true;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\n' {
+            line_start = true;
+            rest = &rest[1..];
+            continue;
+        }
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if c == '#' && line_start {
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+            continue;
+        }
+        line_start = false;
+
+        if rest.starts_with("/*") {
+            rest = rest[2..].find("*/").map_or("", |end| &rest[end + 4..]);
+        } else if rest.starts_with("//") {
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(CToken::Identifier(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if c.is_ascii_digit()
+            || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let end = number_length(rest);
+            tokens.push(CToken::Number(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if c == '"' || c == '\'' {
+            let end = literal_length(rest, c);
+            tokens.push(CToken::Literal(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(punct) = PUNCTUATION.iter().find(|punct| rest.starts_with(*punct)) {
+            tokens.push(CToken::Punct(punct));
+            rest = &rest[punct.len()..];
+        } else {
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    tokens
+}
+
+/// The length of the number at the start of `source`, which is a
+/// preprocessing number: digits, letters, `.` and signed exponents.
+fn number_length(source: &str) -> usize {
+    let bytes = source.as_bytes();
+    let mut end = 0;
+    while end < bytes.len() {
+        let c = bytes[end];
+        let is_sign = (c == b'+' || c == b'-')
+            && end > 0
+            && matches!(bytes[end - 1], b'e' | b'E' | b'p' | b'P');
+        if !(c.is_ascii_alphanumeric() || c == b'_' || c == b'.' || is_sign) {
+            break;
+        }
+        end += 1;
+    }
+    end
+}
+
+/// The length of the literal quoted by `quote` at the start of `source`.
+fn literal_length(source: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in source.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\n' => return i,
+            c if c == quote => return i + 1,
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize("unsigned long x = 0x1FUL << 2; // comment\n/* a\nb */ 'a' \"s\\\"\""),
+            [
+                CToken::Identifier("unsigned".to_string()),
+                CToken::Identifier("long".to_string()),
+                CToken::Identifier("x".to_string()),
+                CToken::Punct("="),
+                CToken::Number("0x1FUL".to_string()),
+                CToken::Punct("<<"),
+                CToken::Number("2".to_string()),
+                CToken::Punct(";"),
+                CToken::Literal("'a'".to_string()),
+                CToken::Literal("\"s\\\"\"".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn directives_are_skipped() {
+        assert_eq!(
+            tokenize("# 1 \"t.h\"\nint x;\n# 2 \"t.h\"\n"),
+            [
+                CToken::Identifier("int".to_string()),
+                CToken::Identifier("x".to_string()),
+                CToken::Punct(";"),
+            ]
+        );
+    }
+}
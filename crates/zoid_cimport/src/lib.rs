@@ -0,0 +1,356 @@
+//! Imports the declarations of C headers for `cimport "header.h";`.
+//!
+//! The header is preprocessed with the system C compiler (`cc -E`, or `$CC`)
+//! and its declarations are translated into Zoid source, which the driver
+//! parses like any other file:
+//!
+//! - function prototypes become `extern "C"` functions,
+//! - enumerators and `#define`s of integer constants become `const`s,
+//! - typedefs are resolved to the types they name,
+//! - structs and unions can only be used through pointers, which are
+//!   imported as `*u8`, as Zoid has no struct types.
+//!
+//! Declarations that cannot be translated, such as functions taking a
+//! `va_list` or a struct by value, are listed in `CImport::skipped`. Names
+//! starting with `__` are reserved for the C implementation and are left
+//! out, as are macros that are not integer constants.
+
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fmt::Write as FmtWrite,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use lexer::tokenize;
+use parser::{CDeclaration, CFunctionType, CType, CValue, IntegerWidths, Parser};
+use zoid_lexer::token::TokenKind;
+
+pub use error::CImportError;
+
+pub mod error;
+mod lexer;
+mod parser;
+
+/// Imports C headers, keeping the result for each header so importing it
+/// again is free.
+#[derive(Debug, Clone)]
+pub struct CImporter {
+    compiler: String,
+    include_dirs: Vec<PathBuf>,
+    /// The macros the compiler defines before reading any header, which
+    /// are not imported.
+    predefined_macros: Option<HashSet<String>>,
+    /// The imports done by this importer, so only for one run of the
+    /// compiler. A header's translation depends on the compiler, its flags
+    /// and every header it includes in turn, so a cache kept between runs
+    /// would need all of those in its key: the header's path and
+    /// modification time alone would miss a changed system header. Running
+    /// the preprocessor is cheap next to compiling, so none is kept.
+    cache: HashMap<String, CImport>,
+}
+
+/// The result of importing a header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CImport {
+    /// Zoid source declaring everything that could be imported.
+    pub source: String,
+    pub skipped: Vec<SkippedDeclaration>,
+}
+
+/// A declaration that could not be translated into Zoid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedDeclaration {
+    /// The name declared, or `None` if the declaration could not be parsed
+    /// far enough to find it.
+    pub name: Option<String>,
+    pub reason: String,
+}
+
+impl CImporter {
+    pub fn new() -> Self {
+        Self {
+            compiler: env::var("CC").unwrap_or_else(|_| "cc".to_string()),
+            include_dirs: Vec::new(),
+            predefined_macros: None,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Sets the C compiler used to preprocess headers, instead of `$CC` or
+    /// `cc`.
+    pub fn with_compiler(mut self, compiler: impl Into<String>) -> Self {
+        self.compiler = compiler.into();
+        self
+    }
+
+    /// Adds a directory to search for headers, before the system ones.
+    pub fn with_include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    /// Imports `header`, as named in `cimport "header";`.
+    pub fn import(&mut self, header: &str) -> Result<&CImport, CImportError> {
+        if !self.cache.contains_key(header) {
+            let import = self.import_uncached(header)?;
+            self.cache.insert(header.to_string(), import);
+        }
+        Ok(&self.cache[header])
+    }
+
+    fn import_uncached(&mut self, header: &str) -> Result<CImport, CImportError> {
+        let include = format!("#include \"{}\"\n", header);
+        let preprocessed = self.preprocess(header, &include, &[])?;
+        let macros = self.preprocess(header, &include, &["-dM"])?;
+        let predefined = self.predefined_macros()?;
+        let macros: Vec<_> = object_like_macros(&macros).collect();
+
+        let widths = IntegerWidths::from_macros(macros.iter().copied());
+        let mut parser = Parser::new(tokenize(&preprocessed)).with_integer_widths(widths);
+        let mut translation = Translation::default();
+        for declaration in parser.parse() {
+            translation.declaration(declaration);
+        }
+
+        // Predefined macros are not imported, but can be used by others, as
+        // `INT_MAX` uses `__INT_MAX__`.
+        for (name, body) in &macros {
+            parser.define_macro(name, tokenize(body));
+        }
+        // Most macros are not constants, such as ones naming functions or
+        // expanding to expressions, so those are not reported.
+        for (name, _) in &macros {
+            if predefined.contains(*name) || translation.names.contains(*name) {
+                continue;
+            }
+            if let Ok(value) = parser.evaluate_macro(name) {
+                if translation.claim(name) {
+                    translation.constant(name, value);
+                }
+            }
+        }
+
+        Ok(CImport {
+            source: translation.source,
+            skipped: translation.skipped,
+        })
+    }
+
+    fn predefined_macros(&mut self) -> Result<HashSet<String>, CImportError> {
+        if self.predefined_macros.is_none() {
+            let macros = self.preprocess("", "", &["-dM"])?;
+            let names = object_like_macros(&macros)
+                .map(|(name, _)| name.to_string())
+                .collect();
+            self.predefined_macros = Some(names);
+        }
+        Ok(self.predefined_macros.clone().unwrap_or_default())
+    }
+
+    /// Runs the preprocessor on `input`, which includes `header`.
+    fn preprocess(&self, header: &str, input: &str, args: &[&str]) -> Result<String, CImportError> {
+        let compiler_error = |error| CImportError::Compiler {
+            compiler: self.compiler.clone(),
+            error,
+        };
+
+        let mut command = Command::new(&self.compiler);
+        command.arg("-E").args(args);
+        for dir in &self.include_dirs {
+            command.arg("-iquote").arg(dir);
+        }
+        command
+            .args(["-x", "c", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = command.spawn().map_err(compiler_error)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes()).map_err(compiler_error)?;
+        }
+        let output = child.wait_with_output().map_err(compiler_error)?;
+
+        if !output.status.success() {
+            return Err(CImportError::Preprocess {
+                header: header.to_string(),
+                message: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl Default for CImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The Zoid declarations for a header, declaring each name at most once.
+#[derive(Default)]
+struct Translation {
+    source: String,
+    skipped: Vec<SkippedDeclaration>,
+    names: HashSet<String>,
+}
+
+impl Translation {
+    fn declaration(&mut self, declaration: CDeclaration) {
+        match declaration {
+            CDeclaration::Function {
+                name,
+                ty,
+                is_static,
+            } => {
+                if !self.claim(&name) {
+                    return;
+                }
+                if is_static {
+                    self.skip(&name, "is `static`, so there is nothing to link against");
+                } else {
+                    self.function(&name, &ty);
+                }
+            }
+            CDeclaration::Variable { name } => {
+                if self.claim(&name) {
+                    self.skip(&name, "is a variable, which cannot be imported");
+                }
+            }
+            CDeclaration::Constant { name, value } => {
+                if !self.claim(&name) {
+                    return;
+                }
+                match value {
+                    Ok(value) => self.constant(&name, value),
+                    Err(reason) => {
+                        self.skip(&name, format!("has a value that {}", reason));
+                    }
+                }
+            }
+            CDeclaration::Unparsed { name, reason } => {
+                if name.as_ref().is_some_and(|name| !self.claim(name)) {
+                    return;
+                }
+                self.skipped.push(SkippedDeclaration {
+                    name,
+                    reason: format!("could not be parsed: {}", reason),
+                });
+            }
+        }
+    }
+
+    /// Whether `name` should be declared: it is not already, is not
+    /// reserved, and is not a Zoid keyword, which is reported.
+    fn claim(&mut self, name: &str) -> bool {
+        if name.starts_with("__") || !self.names.insert(name.to_string()) {
+            return false;
+        }
+        if TokenKind::keyword(name).is_some() {
+            self.skip(
+                name,
+                format!("is named `{}`, which is a keyword in Zoid", name),
+            );
+            return false;
+        }
+        true
+    }
+
+    fn skip(&mut self, name: &str, reason: impl Into<String>) {
+        self.skipped.push(SkippedDeclaration {
+            name: Some(name.to_string()),
+            reason: reason.into(),
+        });
+    }
+
+    fn function(&mut self, name: &str, function: &CFunctionType) {
+        let mut parameters = Vec::new();
+        for (i, parameter) in function.parameters.iter().enumerate() {
+            let ty = match zoid_type(&parameter.ty) {
+                Ok(ty) => ty,
+                Err(ty) => {
+                    let reason = format!(
+                        "has a parameter of type `{}`, which Zoid cannot represent",
+                        ty
+                    );
+                    return self.skip(name, reason);
+                }
+            };
+            let parameter_name = match &parameter.name {
+                Some(name) if TokenKind::keyword(name).is_none() => name.clone(),
+                _ => format!("arg{}", i),
+            };
+            parameters.push(format!("{}: {}", parameter_name, ty));
+        }
+        if function.variadic {
+            parameters.push("...".to_string());
+        }
+
+        let return_type = match &function.return_type {
+            CType::Void => String::new(),
+            ty => match zoid_type(ty) {
+                Ok(ty) => format!(": {}", ty),
+                Err(ty) => {
+                    let reason = format!("returns `{}`, which Zoid cannot represent", ty);
+                    return self.skip(name, reason);
+                }
+            },
+        };
+
+        let _ = writeln!(
+            self.source,
+            "extern \"C\" fn {}({}){};",
+            name,
+            parameters.join(", "),
+            return_type
+        );
+    }
+
+    /// Declares a constant of the Zoid integer type matching its C type.
+    fn constant(&mut self, name: &str, value: CValue) {
+        let sign = if value.signed { 'i' } else { 'u' };
+        let _ = writeln!(
+            self.source,
+            "const {}: {}{} = {};",
+            name, sign, value.bits, value.value
+        );
+    }
+}
+
+/// The Zoid spelling of `ty`, or its C spelling if Zoid has no equivalent.
+fn zoid_type(ty: &CType) -> Result<String, String> {
+    match ty {
+        CType::Bool => Ok("bool".to_string()),
+        CType::Integer { bits, signed } => {
+            Ok(format!("{}{}", if *signed { "i" } else { "u" }, bits))
+        }
+        CType::Float => Ok("f32".to_string()),
+        CType::Double => Ok("f64".to_string()),
+        // `void *`, and pointers to structs, functions and anything else
+        // Zoid cannot describe, are opaque byte pointers.
+        CType::Pointer(pointee) => Ok(format!(
+            "*{}",
+            zoid_type(pointee).unwrap_or_else(|_| "u8".to_string())
+        )),
+        ty => Err(ty.to_string()),
+    }
+}
+
+/// The object-like macros in the output of `cc -E -dM`, with their bodies.
+/// Function-like and empty macros are left out.
+fn object_like_macros(output: &str) -> impl Iterator<Item = (&str, &str)> {
+    output.lines().filter_map(|line| {
+        let definition = line.strip_prefix("#define ")?;
+        let end = definition
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(definition.len());
+        let (name, body) = definition.split_at(end);
+        if body.starts_with('(') || body.trim().is_empty() {
+            return None;
+        }
+        Some((name, body.trim()))
+    })
+}
@@ -0,0 +1,1460 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    mem,
+};
+
+use crate::lexer::CToken;
+
+type ParseResult<T> = Result<T, String>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CType {
+    Void,
+    Bool,
+    Integer {
+        bits: u32,
+        signed: bool,
+    },
+    Float,
+    Double,
+    Pointer(Box<CType>),
+    Array(Box<CType>),
+    Function(Box<CFunctionType>),
+    /// A struct or union, such as `struct tm`. Only the name is kept, as
+    /// Zoid can only refer to one through a pointer.
+    Record(String),
+    /// A type with no Zoid equivalent, such as `long double`, by name.
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CFunctionType {
+    pub return_type: CType,
+    pub parameters: Vec<CParameter>,
+    pub variadic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CParameter {
+    pub name: Option<String>,
+    pub ty: CType,
+}
+
+/// A declaration found in a header. Typedefs, structs and unions are only
+/// kept by the parser, to resolve the types of later declarations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CDeclaration {
+    Function {
+        name: String,
+        ty: CFunctionType,
+        /// Whether the function is `static`, so there is no symbol to link
+        /// against.
+        is_static: bool,
+    },
+    Variable {
+        name: String,
+    },
+    /// An enumerator, or why its value could not be evaluated.
+    Constant {
+        name: String,
+        value: ParseResult<CValue>,
+    },
+    /// A declaration that could not be parsed, named if the name could be
+    /// found.
+    Unparsed {
+        name: Option<String>,
+        reason: String,
+    },
+}
+
+/// The value of an integer constant expression, with its C type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CValue {
+    /// The value, which is in the range of the type.
+    pub value: i128,
+    pub bits: u32,
+    pub signed: bool,
+}
+
+/// The widths in bits of C's integer types, which depend on the target, as
+/// `long` is 32 bits on Windows and on 32-bit targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntegerWidths {
+    pub short: u32,
+    pub int: u32,
+    pub long: u32,
+    pub long_long: u32,
+}
+
+/// The specifiers at the start of a declaration, such as `static const int`.
+struct Specifiers {
+    ty: CType,
+    is_typedef: bool,
+    is_static: bool,
+}
+
+/// The counts of the keywords that make up a basic type, as C allows them
+/// in any order, such as `long unsigned long`.
+#[derive(Default)]
+struct BaseType {
+    void: bool,
+    bool: bool,
+    char: bool,
+    short: bool,
+    int: bool,
+    long: u32,
+    int128: bool,
+    float: bool,
+    double: bool,
+    signed: bool,
+    unsigned: bool,
+    complex: bool,
+}
+
+/// What a declarator does to the type of its specifiers, applied innermost
+/// first.
+#[derive(Debug, Clone)]
+enum Modifier {
+    Pointer,
+    Array,
+    Function(Vec<CParameter>, bool),
+}
+
+struct Declarator {
+    name: Option<String>,
+    modifiers: Vec<Modifier>,
+}
+
+/// Words that are ignored wherever they appear among specifiers or after a
+/// `*`, as they do not change how a value is passed.
+const QUALIFIERS: &[&str] = &[
+    "const",
+    "__const",
+    "__const__",
+    "volatile",
+    "__volatile",
+    "__volatile__",
+    "restrict",
+    "__restrict",
+    "__restrict__",
+    "_Nullable",
+    "_Nonnull",
+    "_Null_unspecified",
+    "_Atomic",
+];
+
+/// GNU extensions that take a parenthesized argument and can be skipped,
+/// such as `__attribute__((nonnull))` or the `__asm__("name")` labels glibc
+/// puts on some functions.
+const EXTENSIONS: &[&str] = &[
+    "__attribute__",
+    "__attribute",
+    "__asm__",
+    "__asm",
+    "asm",
+    "__declspec",
+    "_Alignas",
+    "alignas",
+];
+
+const TYPE_KEYWORDS: &[&str] = &[
+    "void",
+    "_Bool",
+    "bool",
+    "char",
+    "short",
+    "int",
+    "long",
+    "__int128",
+    "float",
+    "double",
+    "signed",
+    "__signed",
+    "__signed__",
+    "unsigned",
+    "_Complex",
+    "__complex__",
+    "struct",
+    "union",
+    "enum",
+    "typeof",
+    "__typeof",
+    "__typeof__",
+];
+
+const STORAGE_KEYWORDS: &[&str] = &[
+    "typedef",
+    "static",
+    "extern",
+    "inline",
+    "__inline",
+    "__inline__",
+    "register",
+    "auto",
+    "_Noreturn",
+    "__extension__",
+    "_Thread_local",
+    "__thread",
+];
+
+/// Parses the declarations of preprocessed C, skipping anything it does
+/// not understand. Function bodies and struct fields are skipped rather than
+/// parsed.
+pub struct Parser {
+    tokens: Vec<CToken>,
+    pos: usize,
+    widths: IntegerWidths,
+    typedefs: HashMap<String, CType>,
+    /// The value of every enumerator so far, for later constant expressions.
+    constants: HashMap<String, CValue>,
+    /// The bodies of object-like macros, for constant expressions.
+    macros: HashMap<String, Vec<CToken>>,
+    declarations: Vec<CDeclaration>,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<CToken>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            widths: IntegerWidths::default(),
+            typedefs: HashMap::new(),
+            constants: HashMap::new(),
+            macros: HashMap::new(),
+            declarations: Vec::new(),
+        }
+    }
+
+    /// Sets the widths of the integer types, instead of those of LP64 targets
+    /// such as 64-bit Linux and macOS.
+    pub fn with_integer_widths(mut self, widths: IntegerWidths) -> Self {
+        self.widths = widths;
+        self
+    }
+
+    /// Parses every declaration. A declaration that cannot be parsed is
+    /// skipped up to its `;` or body and returned as `Unparsed`.
+    pub fn parse(&mut self) -> Vec<CDeclaration> {
+        while self.pos < self.tokens.len() {
+            let start = self.pos;
+            if let Err(reason) = self.parse_declaration() {
+                self.pos = start;
+                let name = self.skip_declaration();
+                self.declarations
+                    .push(CDeclaration::Unparsed { name, reason });
+            }
+        }
+
+        mem::take(&mut self.declarations)
+    }
+
+    pub fn define_macro(&mut self, name: &str, body: Vec<CToken>) {
+        self.macros.insert(name.to_string(), body);
+    }
+
+    /// Evaluates the object-like macro `name` as an integer constant
+    /// expression, using the typedefs and enumerators parsed so far. The
+    /// macros it uses are expanded into its tokens first, as the
+    /// preprocessor does, so with `#define A 1 + 2`, `#define B A * 3` is
+    /// `1 + 2 * 3`.
+    pub fn evaluate_macro(&mut self, name: &str) -> ParseResult<CValue> {
+        if !self.macros.contains_key(name) {
+            return Err(format!("`{}` is not a macro", name));
+        }
+        let body = self.expand(&[CToken::Identifier(name.to_string())], &mut Vec::new());
+
+        let saved_tokens = mem::replace(&mut self.tokens, body);
+        let saved_pos = mem::replace(&mut self.pos, 0);
+
+        let result = self.evaluate_expression().and_then(|value| {
+            if self.pos < self.tokens.len() {
+                Err("is not a constant expression".to_string())
+            } else {
+                Ok(value)
+            }
+        });
+
+        self.tokens = saved_tokens;
+        self.pos = saved_pos;
+        result
+    }
+
+    /// Replaces the object-like macros in `tokens` with their expanded
+    /// bodies. The macros in `hidden` are being expanded, so are left as
+    /// they are, as C does not expand a macro again within itself.
+    fn expand(&self, tokens: &[CToken], hidden: &mut Vec<String>) -> Vec<CToken> {
+        let mut expanded = Vec::new();
+        for tok in tokens {
+            let definition = match tok {
+                CToken::Identifier(word) if !hidden.contains(word) => {
+                    self.macros.get(word).map(|body| (word, body))
+                }
+                _ => None,
+            };
+            match definition {
+                Some((name, body)) => {
+                    hidden.push(name.clone());
+                    expanded.extend(self.expand(body, hidden));
+                    hidden.pop();
+                }
+                None => expanded.push(tok.clone()),
+            }
+        }
+        expanded
+    }
+
+    fn peek(&self) -> Option<&CToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_word(&self) -> Option<&str> {
+        match self.peek() {
+            Some(CToken::Identifier(word)) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn next_is_punct(&self, punct: &str) -> bool {
+        self.peek().is_some_and(|tok| tok.is_punct(punct))
+    }
+
+    fn nth_is_punct(&self, n: usize, punct: &str) -> bool {
+        self.tokens
+            .get(self.pos + n)
+            .is_some_and(|tok| tok.is_punct(punct))
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        let found = self.next_is_punct(punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> ParseResult<()> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(match self.peek() {
+                Some(tok) => format!("expected `{}`, found {}", punct, describe(tok)),
+                None => format!("expected `{}`, found end of file", punct),
+            })
+        }
+    }
+
+    /// Whether `word` can start a type, as opposed to naming a declarator.
+    fn is_type_word(&self, word: &str) -> bool {
+        TYPE_KEYWORDS.contains(&word)
+            || QUALIFIERS.contains(&word)
+            || STORAGE_KEYWORDS.contains(&word)
+            || self.typedefs.contains_key(word)
+    }
+
+    fn parse_declaration(&mut self) -> ParseResult<()> {
+        if self.eat_punct(";") {
+            return Ok(());
+        }
+        if matches!(self.peek_word(), Some("_Static_assert" | "static_assert")) {
+            self.skip_declaration();
+            return Ok(());
+        }
+
+        let specifiers = self.parse_specifiers()?;
+        // A struct, union or enum on its own.
+        if self.eat_punct(";") {
+            return Ok(());
+        }
+
+        loop {
+            let declarator = self.parse_declarator()?;
+            self.skip_extensions()?;
+            let Some(name) = declarator.name.clone() else {
+                return Err("expected a name".to_string());
+            };
+            let ty = declarator.apply(specifiers.ty.clone());
+
+            // A function definition, which ends the declaration.
+            if self.next_is_punct("{") {
+                self.skip_balanced()?;
+                self.declare(name, ty, &specifiers);
+                return Ok(());
+            }
+
+            if self.eat_punct("=") {
+                self.skip_until(&[",", ";"]);
+            }
+            self.declare(name, ty, &specifiers);
+
+            if !self.eat_punct(",") {
+                return self.expect_punct(";");
+            }
+        }
+    }
+
+    fn declare(&mut self, name: String, ty: CType, specifiers: &Specifiers) {
+        if specifiers.is_typedef {
+            // Name anonymous structs after their typedef, as in
+            // `typedef struct { ... } div_t;`.
+            let ty = match ty {
+                CType::Record(record) if record.ends_with("<anonymous>") => {
+                    CType::Record(name.clone())
+                }
+                ty => ty,
+            };
+            self.typedefs.insert(name, ty);
+            return;
+        }
+
+        let declaration = match ty {
+            CType::Function(function) => CDeclaration::Function {
+                name,
+                ty: *function,
+                is_static: specifiers.is_static,
+            },
+            _ => CDeclaration::Variable { name },
+        };
+        self.declarations.push(declaration);
+    }
+
+    fn parse_specifiers(&mut self) -> ParseResult<Specifiers> {
+        let mut base = BaseType::default();
+        let mut named = None;
+        let mut is_typedef = false;
+        let mut is_static = false;
+
+        while let Some(word) = self.peek_word().map(str::to_string) {
+            match word.as_str() {
+                "typedef" => is_typedef = true,
+                "static" => is_static = true,
+                "_Atomic" if self.nth_is_punct(1, "(") => {
+                    self.pos += 1;
+                    self.skip_balanced()?;
+                    named = Some(CType::Unsupported("_Atomic".to_string()));
+                    continue;
+                }
+                word if STORAGE_KEYWORDS.contains(&word) || QUALIFIERS.contains(&word) => {}
+                word if EXTENSIONS.contains(&word) => {
+                    self.skip_extensions()?;
+                    continue;
+                }
+                "void" => base.void = true,
+                "_Bool" | "bool" => base.bool = true,
+                "char" => base.char = true,
+                "short" => base.short = true,
+                "int" => base.int = true,
+                "long" => base.long += 1,
+                "__int128" => base.int128 = true,
+                "float" => base.float = true,
+                "double" => base.double = true,
+                "signed" | "__signed" | "__signed__" => base.signed = true,
+                "unsigned" => base.unsigned = true,
+                "_Complex" | "__complex__" => base.complex = true,
+                "struct" | "union" => {
+                    named = Some(self.parse_record()?);
+                    continue;
+                }
+                "enum" => {
+                    named = Some(self.parse_enum()?);
+                    continue;
+                }
+                "typeof" | "__typeof" | "__typeof__" => {
+                    self.pos += 1;
+                    self.skip_balanced()?;
+                    named = Some(CType::Unsupported(word));
+                    continue;
+                }
+                // Any other name before the type is a typedef. Old style
+                // declarations with an implicit `int` are not supported.
+                _ if base.is_empty() && named.is_none() => {
+                    named = Some(
+                        self.typedefs
+                            .get(&word)
+                            .cloned()
+                            .unwrap_or_else(|| builtin_type(&word)),
+                    );
+                }
+                _ => break,
+            }
+            self.pos += 1;
+        }
+
+        let ty = match named {
+            Some(ty) => ty,
+            None => base.resolve(&self.widths)?,
+        };
+        Ok(Specifiers {
+            ty,
+            is_typedef,
+            is_static,
+        })
+    }
+
+    /// Parses `struct tag { ... }` or `union tag`. The fields are skipped.
+    fn parse_record(&mut self) -> ParseResult<CType> {
+        let keyword = self.peek_word().unwrap_or_default().to_string();
+        self.pos += 1;
+        self.skip_extensions()?;
+
+        let tag = match self.peek_word() {
+            Some(tag) => {
+                let tag = tag.to_string();
+                self.pos += 1;
+                tag
+            }
+            None => "<anonymous>".to_string(),
+        };
+        self.skip_extensions()?;
+        if self.next_is_punct("{") {
+            self.skip_balanced()?;
+        }
+
+        Ok(CType::Record(format!("{} {}", keyword, tag)))
+    }
+
+    /// Parses `enum tag { A, B = 2 }`, declaring each enumerator as a
+    /// constant. Enums are imported as `int`, their type in C.
+    fn parse_enum(&mut self) -> ParseResult<CType> {
+        self.pos += 1;
+        self.skip_extensions()?;
+        if self.peek_word().is_some() {
+            self.pos += 1;
+        }
+        // A fixed underlying type, as in C23's `enum E : unsigned char`.
+        if self.eat_punct(":") {
+            self.parse_specifiers()?;
+        }
+        self.skip_extensions()?;
+
+        if self.eat_punct("{") {
+            let widths = self.widths;
+            let mut next = Ok(widths.int(0));
+            while !self.eat_punct("}") {
+                let Some(name) = self.peek_word().map(str::to_string) else {
+                    return Err("expected an enumerator".to_string());
+                };
+                self.pos += 1;
+                self.skip_extensions()?;
+
+                let value = if self.eat_punct("=") {
+                    let value = self.evaluate_expression();
+                    if value.is_err() {
+                        self.skip_until(&[",", "}"]);
+                    }
+                    value
+                } else {
+                    next
+                };
+                // An enumerator is an `int`, or has the type of its value
+                // if that does not fit in one, as GCC allows.
+                let value = value.map(|value| match widths.int(value.value) {
+                    int if int.fits() => int,
+                    _ => value,
+                });
+                if let Ok(value) = value {
+                    self.constants.insert(name.clone(), value);
+                }
+                next = value
+                    .clone()
+                    .and_then(|value| apply_binary("+", value, widths.int(1), &widths));
+                self.declarations
+                    .push(CDeclaration::Constant { name, value });
+
+                if !self.eat_punct(",") {
+                    self.expect_punct("}")?;
+                    break;
+                }
+            }
+        }
+
+        Ok(CType::Integer {
+            bits: self.widths.int,
+            signed: true,
+        })
+    }
+
+    /// Parses a declarator, such as `*name`, `(*name)(int)` or `[4]`. The
+    /// name is optional, as parameters and casts can leave it out.
+    fn parse_declarator(&mut self) -> ParseResult<Declarator> {
+        let mut pointers = 0;
+        loop {
+            self.skip_qualifiers()?;
+            if !self.eat_punct("*") {
+                break;
+            }
+            pointers += 1;
+        }
+
+        let mut name = None;
+        let mut inner = None;
+        if self.next_is_punct("(") && self.starts_nested_declarator() {
+            self.pos += 1;
+            inner = Some(self.parse_declarator()?);
+            self.expect_punct(")")?;
+        } else if let Some(word) = self.peek_word() {
+            if !self.is_type_word(word) && !EXTENSIONS.contains(&word) {
+                name = Some(word.to_string());
+                self.pos += 1;
+            }
+        }
+
+        let mut suffixes = Vec::new();
+        loop {
+            self.skip_extensions()?;
+            if self.next_is_punct("[") {
+                self.skip_balanced()?;
+                suffixes.push(Modifier::Array);
+            } else if self.eat_punct("(") {
+                let (parameters, variadic) = self.parse_parameters()?;
+                suffixes.push(Modifier::Function(parameters, variadic));
+            } else {
+                break;
+            }
+        }
+
+        // The pointers apply to the specifiers' type, then the suffixes from
+        // the right, then whatever the parenthesized declarator does.
+        let mut modifiers = vec![Modifier::Pointer; pointers];
+        modifiers.extend(suffixes.into_iter().rev());
+        if let Some(inner) = inner {
+            name = inner.name;
+            modifiers.extend(inner.modifiers);
+        }
+
+        Ok(Declarator { name, modifiers })
+    }
+
+    /// Whether the `(` at the current token starts a parenthesized
+    /// declarator, as in `(*name)`, rather than a parameter list.
+    fn starts_nested_declarator(&self) -> bool {
+        match self.tokens.get(self.pos + 1) {
+            Some(CToken::Punct("*" | "(" | "^")) => true,
+            Some(CToken::Identifier(word)) => !self.is_type_word(word),
+            _ => false,
+        }
+    }
+
+    /// Parses the parameters of a function after the `(`, returning them
+    /// and whether the function is variadic.
+    fn parse_parameters(&mut self) -> ParseResult<(Vec<CParameter>, bool)> {
+        let mut parameters = Vec::new();
+
+        // An empty list means no parameters, as in C23 and C++.
+        if self.eat_punct(")") {
+            return Ok((parameters, false));
+        }
+        if self.peek_word() == Some("void") && self.nth_is_punct(1, ")") {
+            self.pos += 2;
+            return Ok((parameters, false));
+        }
+
+        loop {
+            if self.eat_punct("...") {
+                self.expect_punct(")")?;
+                return Ok((parameters, true));
+            }
+
+            let specifiers = self.parse_specifiers()?;
+            let declarator = self.parse_declarator()?;
+            let name = declarator.name.clone();
+            // Array and function parameters are really pointers.
+            let ty = match declarator.apply(specifiers.ty) {
+                CType::Array(element) => CType::Pointer(element),
+                CType::Function(function) => CType::Pointer(Box::new(CType::Function(function))),
+                ty => ty,
+            };
+            parameters.push(CParameter { name, ty });
+
+            if self.eat_punct(")") {
+                return Ok((parameters, false));
+            }
+            self.expect_punct(",")?;
+        }
+    }
+
+    fn skip_qualifiers(&mut self) -> ParseResult<()> {
+        loop {
+            match self.peek_word() {
+                Some("_Atomic") if self.nth_is_punct(1, "(") => return Ok(()),
+                Some(word) if QUALIFIERS.contains(&word) => self.pos += 1,
+                Some(word) if EXTENSIONS.contains(&word) => self.skip_extensions()?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn skip_extensions(&mut self) -> ParseResult<()> {
+        while let Some(word) = self.peek_word() {
+            if !EXTENSIONS.contains(&word) {
+                break;
+            }
+            self.pos += 1;
+            if self.next_is_punct("(") {
+                self.skip_balanced()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips from an opening bracket to just past the matching one.
+    fn skip_balanced(&mut self) -> ParseResult<()> {
+        let mut depth = 0;
+        while let Some(tok) = self.peek() {
+            match tok {
+                CToken::Punct("(" | "[" | "{") => depth += 1,
+                CToken::Punct(")" | "]" | "}") => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+        Err("unbalanced brackets".to_string())
+    }
+
+    /// Skips to the first of `puncts` outside of any brackets, without
+    /// consuming it.
+    fn skip_until(&mut self, puncts: &[&str]) {
+        let mut depth = 0;
+        while let Some(tok) = self.peek() {
+            match tok {
+                CToken::Punct(p) if depth == 0 && puncts.contains(p) => return,
+                CToken::Punct("(" | "[" | "{") => depth += 1,
+                CToken::Punct(")" | "]" | "}") if depth == 0 => return,
+                CToken::Punct(")" | "]" | "}") => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skips the rest of a declaration that could not be parsed: up to and
+    /// including its `;`, or the end of its body if it is a function
+    /// definition. Returns the most likely name of what was declared: the
+    /// first name followed by `(`, or otherwise the last name.
+    fn skip_declaration(&mut self) -> Option<String> {
+        let mut depth = 0;
+        let mut function_name = None;
+        let mut last_name = None;
+        let mut previous: Option<CToken> = None;
+
+        while let Some(tok) = self.peek().cloned() {
+            self.pos += 1;
+            match &tok {
+                CToken::Punct(";") if depth == 0 => break,
+                CToken::Punct("(" | "[" | "{") => {
+                    if depth == 0 && tok.is_punct("(") {
+                        if let Some(CToken::Identifier(word)) = &previous {
+                            if !EXTENSIONS.contains(&word.as_str()) && function_name.is_none() {
+                                function_name = Some(word.clone());
+                            }
+                        }
+                    }
+                    depth += 1;
+                }
+                CToken::Punct(")" | "]" | "}") => {
+                    depth -= 1;
+                    // The end of a function body, which has no `;`.
+                    if depth <= 0 && tok.is_punct("}") && function_name.is_some() {
+                        break;
+                    }
+                }
+                CToken::Identifier(word) if depth == 0 && !self.is_type_word(word) => {
+                    last_name = Some(word.clone());
+                }
+                _ => {}
+            }
+            previous = Some(tok);
+        }
+
+        function_name.or(last_name)
+    }
+
+    fn evaluate_expression(&mut self) -> ParseResult<CValue> {
+        let condition = self.evaluate_binary(0)?;
+        if !self.eat_punct("?") {
+            return Ok(condition);
+        }
+        let then = self.evaluate_expression()?;
+        self.expect_punct(":")?;
+        let otherwise = self.evaluate_expression()?;
+        // The result has the common type of both branches, whichever is
+        // taken.
+        let (bits, signed) = self.widths.common_type(then, otherwise);
+        let value = if condition.value != 0 {
+            then
+        } else {
+            otherwise
+        };
+        CValue::new(value.value, bits, signed)
+    }
+
+    /// Evaluates binary operators binding tighter than `min_precedence`.
+    fn evaluate_binary(&mut self, min_precedence: u8) -> ParseResult<CValue> {
+        let mut lhs = self.evaluate_unary()?;
+
+        while let Some(CToken::Punct(op)) = self.peek() {
+            let op = *op;
+            let Some(precedence) = binary_precedence(op) else {
+                break;
+            };
+            if precedence <= min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.evaluate_binary(precedence)?;
+            lhs = apply_binary(op, lhs, rhs, &self.widths)?;
+        }
+
+        Ok(lhs)
+    }
+
+    fn evaluate_unary(&mut self) -> ParseResult<CValue> {
+        let Some(tok) = self.peek().cloned() else {
+            return Err("is empty".to_string());
+        };
+        self.pos += 1;
+
+        match tok {
+            CToken::Punct("-") => {
+                let value = self.evaluate_unary()?;
+                let value = self.widths.promote(value);
+                if value.signed {
+                    value.checked(value.value.checked_neg())
+                } else {
+                    CValue::new(value.value.wrapping_neg(), value.bits, false)
+                }
+            }
+            CToken::Punct("+") => {
+                let value = self.evaluate_unary()?;
+                Ok(self.widths.promote(value))
+            }
+            CToken::Punct("~") => {
+                let value = self.evaluate_unary()?;
+                let value = self.widths.promote(value);
+                CValue::new(!value.value, value.bits, value.signed)
+            }
+            CToken::Punct("!") => {
+                let value = self.evaluate_unary()?;
+                Ok(self.widths.int((value.value == 0) as i128))
+            }
+            CToken::Punct("(") => {
+                let is_cast = self.peek_word().is_some_and(|word| self.is_type_word(word));
+                if is_cast {
+                    let specifiers = self.parse_specifiers()?;
+                    let ty = self.parse_declarator()?.apply(specifiers.ty);
+                    self.expect_punct(")")?;
+                    let value = self.evaluate_unary()?;
+                    cast(value, &ty, &self.widths)
+                } else {
+                    let value = self.evaluate_expression()?;
+                    self.expect_punct(")")?;
+                    Ok(value)
+                }
+            }
+            CToken::Number(number) => parse_integer(&number, &self.widths),
+            CToken::Literal(literal) if literal.starts_with('\'') => {
+                Ok(self.widths.int(parse_character(&literal)?))
+            }
+            CToken::Identifier(word) => match self.constants.get(&word) {
+                Some(value) => Ok(*value),
+                None => Err(format!("uses `{}`", word)),
+            },
+            tok => Err(format!("uses {}", describe(&tok))),
+        }
+    }
+}
+
+impl BaseType {
+    fn is_empty(&self) -> bool {
+        !(self.void
+            || self.bool
+            || self.char
+            || self.short
+            || self.int
+            || self.long > 0
+            || self.int128
+            || self.float
+            || self.double
+            || self.signed
+            || self.unsigned
+            || self.complex)
+    }
+
+    fn resolve(&self, widths: &IntegerWidths) -> ParseResult<CType> {
+        let integer = |bits| CType::Integer {
+            bits,
+            signed: !self.unsigned,
+        };
+
+        let ty = if self.complex {
+            CType::Unsupported("_Complex".to_string())
+        } else if self.void {
+            CType::Void
+        } else if self.bool {
+            CType::Bool
+        } else if self.float {
+            CType::Float
+        } else if self.double && self.long > 0 {
+            CType::Unsupported("long double".to_string())
+        } else if self.double {
+            CType::Double
+        } else if self.char {
+            // Plain `char` is imported as `u8`, so C strings are `*u8`.
+            CType::Integer {
+                bits: 8,
+                signed: self.signed,
+            }
+        } else if self.short {
+            integer(widths.short)
+        } else if self.int128 {
+            integer(128)
+        } else if self.long > 1 {
+            integer(widths.long_long)
+        } else if self.long == 1 {
+            integer(widths.long)
+        } else if self.int || self.signed || self.unsigned {
+            integer(widths.int)
+        } else {
+            return Err("expected a type".to_string());
+        };
+
+        Ok(ty)
+    }
+}
+
+impl IntegerWidths {
+    /// The widths given by the `__SIZEOF_SHORT__`, `__SIZEOF_INT__`,
+    /// `__SIZEOF_LONG__` and `__SIZEOF_LONG_LONG__` macros that GCC and
+    /// Clang predefine, keeping LP64's for any that are missing.
+    pub fn from_macros<'a>(macros: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut widths = Self::default();
+        for (name, body) in macros {
+            let width = match name {
+                "__SIZEOF_SHORT__" => &mut widths.short,
+                "__SIZEOF_INT__" => &mut widths.int,
+                "__SIZEOF_LONG__" => &mut widths.long,
+                "__SIZEOF_LONG_LONG__" => &mut widths.long_long,
+                _ => continue,
+            };
+            if let Ok(bytes) = body.parse::<u32>() {
+                *width = bytes * 8;
+            }
+        }
+        widths
+    }
+}
+
+impl IntegerWidths {
+    /// An `int` of `value`, such as the result of a comparison.
+    fn int(&self, value: i128) -> CValue {
+        CValue {
+            value,
+            bits: self.int,
+            signed: true,
+        }
+    }
+
+    /// Applies the integer promotions, which convert values of types
+    /// narrower than `int` to `int`.
+    fn promote(&self, value: CValue) -> CValue {
+        if value.bits < self.int {
+            self.int(value.value)
+        } else {
+            value
+        }
+    }
+
+    /// The type that the usual arithmetic conversions convert both operands
+    /// of a binary operator to, as its width and signedness.
+    fn common_type(&self, lhs: CValue, rhs: CValue) -> (u32, bool) {
+        let (lhs, rhs) = (self.promote(lhs), self.promote(rhs));
+        if lhs.signed == rhs.signed {
+            return (lhs.bits.max(rhs.bits), lhs.signed);
+        }
+        let (signed, unsigned) = if lhs.signed { (lhs, rhs) } else { (rhs, lhs) };
+        // The signed type only wins if it can hold every value of the
+        // unsigned one, as `long` can `unsigned int` on LP64.
+        if signed.bits > unsigned.bits {
+            (signed.bits, true)
+        } else {
+            (unsigned.bits, false)
+        }
+    }
+}
+
+impl CValue {
+    /// `value` converted to the integer type of the given width and
+    /// signedness, wrapping it as C does. Unsigned 128-bit values are kept in
+    /// an `i128`, so cannot be above `i128::MAX`.
+    fn new(value: i128, bits: u32, signed: bool) -> ParseResult<CValue> {
+        let value = match bits {
+            128 if !signed && value < 0 => return Err(overflow()),
+            128 => value,
+            bits => {
+                let wrapped = value & ((1 << bits) - 1);
+                if signed && wrapped >> (bits - 1) == 1 {
+                    wrapped - (1 << bits)
+                } else {
+                    wrapped
+                }
+            }
+        };
+        Ok(CValue {
+            value,
+            bits,
+            signed,
+        })
+    }
+
+    /// Whether the value is in the range of the type.
+    fn fits(&self) -> bool {
+        match (self.bits, self.signed) {
+            (128, true) => true,
+            (128, false) => self.value >= 0,
+            (bits, true) => (-(1 << (bits - 1))..1 << (bits - 1)).contains(&self.value),
+            (bits, false) => (0..1 << bits).contains(&self.value),
+        }
+    }
+
+    /// The result of signed arithmetic in the type of `self`, which must not
+    /// overflow.
+    fn checked(&self, value: Option<i128>) -> ParseResult<CValue> {
+        value
+            .map(|value| CValue { value, ..*self })
+            .filter(CValue::fits)
+            .ok_or_else(overflow)
+    }
+}
+
+impl Default for IntegerWidths {
+    fn default() -> Self {
+        Self {
+            short: 16,
+            int: 32,
+            long: 64,
+            long_long: 64,
+        }
+    }
+}
+
+impl Declarator {
+    fn apply(self, mut ty: CType) -> CType {
+        for modifier in self.modifiers {
+            ty = match modifier {
+                Modifier::Pointer => CType::Pointer(Box::new(ty)),
+                Modifier::Array => CType::Array(Box::new(ty)),
+                Modifier::Function(parameters, variadic) => {
+                    CType::Function(Box::new(CFunctionType {
+                        return_type: ty,
+                        parameters,
+                        variadic,
+                    }))
+                }
+            };
+        }
+        ty
+    }
+}
+
+impl Display for CType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CType::Void => write!(f, "void"),
+            CType::Bool => write!(f, "_Bool"),
+            CType::Integer { bits, signed } => {
+                if !signed {
+                    write!(f, "unsigned ")?;
+                }
+                match bits {
+                    8 if *signed => write!(f, "signed char"),
+                    8 => write!(f, "char"),
+                    16 => write!(f, "short"),
+                    32 => write!(f, "int"),
+                    64 => write!(f, "long"),
+                    _ => write!(f, "__int128"),
+                }
+            }
+            CType::Float => write!(f, "float"),
+            CType::Double => write!(f, "double"),
+            CType::Pointer(pointee) => write!(f, "{} *", pointee),
+            CType::Array(element) => write!(f, "{}[]", element),
+            CType::Function(function) => write!(f, "{} (...)", function.return_type),
+            CType::Record(name) | CType::Unsupported(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Compiler builtin types, which have no typedef in the headers.
+fn builtin_type(name: &str) -> CType {
+    match name {
+        "_Float32" => CType::Float,
+        "_Float64" | "_Float32x" => CType::Double,
+        _ => CType::Unsupported(name.to_string()),
+    }
+}
+
+fn describe(tok: &CToken) -> String {
+    match tok {
+        CToken::Identifier(word) | CToken::Number(word) | CToken::Literal(word) => {
+            format!("`{}`", word)
+        }
+        CToken::Punct(punct) => format!("`{}`", punct),
+    }
+}
+
+fn overflow() -> String {
+    "overflows".to_string()
+}
+
+fn binary_precedence(op: &str) -> Option<u8> {
+    let precedence = match op {
+        "||" => 1,
+        "&&" => 2,
+        "|" => 3,
+        "^" => 4,
+        "&" => 5,
+        "==" | "!=" => 6,
+        "<" | ">" | "<=" | ">=" => 7,
+        "<<" | ">>" => 8,
+        "+" | "-" => 9,
+        "*" | "/" | "%" => 10,
+        _ => return None,
+    };
+    Some(precedence)
+}
+
+/// Applies a binary operator as C does: the operands are converted to their
+/// common type, in which unsigned arithmetic wraps and signed arithmetic
+/// must not overflow.
+fn apply_binary(op: &str, lhs: CValue, rhs: CValue, widths: &IntegerWidths) -> ParseResult<CValue> {
+    let truth = |value: bool| Ok(widths.int(value as i128));
+    match op {
+        "||" => return truth(lhs.value != 0 || rhs.value != 0),
+        "&&" => return truth(lhs.value != 0 && rhs.value != 0),
+        // The operands of shifts are promoted on their own, and the result
+        // has the type of the left one.
+        "<<" | ">>" => {
+            let lhs = widths.promote(lhs);
+            let shift = u32::try_from(rhs.value)
+                .ok()
+                .filter(|shift| *shift < lhs.bits)
+                .ok_or_else(overflow)?;
+            return match op {
+                ">>" => Ok(CValue {
+                    value: lhs.value >> shift,
+                    ..lhs
+                }),
+                // GCC wraps signed left shifts too, which headers rely on
+                // for flags such as `1 << 31`.
+                _ => CValue::new(lhs.value.wrapping_shl(shift), lhs.bits, lhs.signed),
+            };
+        }
+        _ => {}
+    }
+
+    let (bits, signed) = widths.common_type(lhs, rhs);
+    let ty = CValue {
+        value: 0,
+        bits,
+        signed,
+    };
+    let lhs = CValue::new(lhs.value, bits, signed)?.value;
+    let rhs = CValue::new(rhs.value, bits, signed)?.value;
+    if rhs == 0 && matches!(op, "/" | "%") {
+        return Err("divides by zero".to_string());
+    }
+
+    let value = match op {
+        "==" => return truth(lhs == rhs),
+        "!=" => return truth(lhs != rhs),
+        "<" => return truth(lhs < rhs),
+        ">" => return truth(lhs > rhs),
+        "<=" => return truth(lhs <= rhs),
+        ">=" => return truth(lhs >= rhs),
+        "|" => return CValue::new(lhs | rhs, bits, signed),
+        "^" => return CValue::new(lhs ^ rhs, bits, signed),
+        "&" => return CValue::new(lhs & rhs, bits, signed),
+        // Unsigned values wrap, and two's complement wrapping in `i128`
+        // keeps the low bits right.
+        "+" if !signed => lhs.wrapping_add(rhs),
+        "-" if !signed => lhs.wrapping_sub(rhs),
+        "*" if !signed => lhs.wrapping_mul(rhs),
+        "+" => return ty.checked(lhs.checked_add(rhs)),
+        "-" => return ty.checked(lhs.checked_sub(rhs)),
+        "*" => return ty.checked(lhs.checked_mul(rhs)),
+        "/" => return ty.checked(lhs.checked_div(rhs)),
+        "%" => return ty.checked(lhs.checked_rem(rhs)),
+        _ => unreachable!("not a binary operator: {}", op),
+    };
+    CValue::new(value, bits, signed)
+}
+
+/// Converts `value` to `ty`, wrapping it as C does for integers.
+fn cast(value: CValue, ty: &CType, widths: &IntegerWidths) -> ParseResult<CValue> {
+    match ty {
+        // A `_Bool` is promoted to `int` wherever it is used.
+        CType::Bool => Ok(widths.int((value.value != 0) as i128)),
+        CType::Integer { bits, signed } => CValue::new(value.value, *bits, *signed),
+        ty => Err(format!("casts to `{}`", ty)),
+    }
+}
+
+/// Parses an integer literal such as `42`, `0x1Fu` or `0755L`, giving it
+/// the first type that can hold it: `int`, `long` or `long long`, as limited
+/// by an `L` or `LL` suffix, which are unsigned with a `U` suffix. Octal and
+/// hexadecimal literals can also have the unsigned version of each.
+fn parse_integer(literal: &str, widths: &IntegerWidths) -> ParseResult<CValue> {
+    let digits = literal.trim_end_matches(['u', 'U', 'l', 'L']);
+    let suffix = literal[digits.len()..].to_ascii_lowercase();
+    let digits = digits.replace('\'', "");
+    let (digits, radix) = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        (hex, 16)
+    } else if let Some(binary) = digits
+        .strip_prefix("0b")
+        .or_else(|| digits.strip_prefix("0B"))
+    {
+        (binary, 2)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        (&digits[1..], 8)
+    } else {
+        (digits.as_str(), 10)
+    };
+    let value = i128::from_str_radix(digits, radix).map_err(|_| format!("uses `{}`", literal))?;
+
+    let unsigned = suffix.contains('u');
+    let candidates = match suffix.matches('l').count() {
+        0 => &[widths.int, widths.long, widths.long_long][..],
+        1 => &[widths.long, widths.long_long][..],
+        _ => &[widths.long_long][..],
+    };
+    candidates
+        .iter()
+        .flat_map(|bits| {
+            let signed = (!unsigned).then_some((*bits, true));
+            let unsigned = (unsigned || radix != 10).then_some((*bits, false));
+            signed.into_iter().chain(unsigned)
+        })
+        .map(|(bits, signed)| CValue {
+            value,
+            bits,
+            signed,
+        })
+        .find(CValue::fits)
+        .ok_or_else(|| format!("uses `{}`, which is too large", literal))
+}
+
+/// Parses a character literal such as `'a'` or `'\n'`, which is an `int`.
+fn parse_character(literal: &str) -> ParseResult<i128> {
+    let inner = &literal[1..literal.len().saturating_sub(1).max(1)];
+    let value = match inner {
+        "\\n" => '\n',
+        "\\t" => '\t',
+        "\\r" => '\r',
+        "\\0" => '\0',
+        "\\\\" => '\\',
+        "\\'" => '\'',
+        _ => {
+            let mut chars = inner.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii() => c,
+                _ => return Err(format!("uses `{}`", literal)),
+            }
+        }
+    };
+    Ok(value as i128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+
+    fn int(bits: u32, signed: bool) -> CType {
+        CType::Integer { bits, signed }
+    }
+
+    /// Evaluates `name` after defining each of `macros`, given as
+    /// `(name, body)`, with LP64 widths.
+    fn evaluate(macros: &[(&str, &str)], name: &str) -> ParseResult<CValue> {
+        let mut parser = Parser::new(Vec::new());
+        for (name, body) in macros {
+            parser.define_macro(name, tokenize(body));
+        }
+        parser.evaluate_macro(name)
+    }
+
+    fn value(value: i128, bits: u32, signed: bool) -> ParseResult<CValue> {
+        Ok(CValue {
+            value,
+            bits,
+            signed,
+        })
+    }
+
+    #[test]
+    fn function_declarations() {
+        let declarations = Parser::new(tokenize(
+            "typedef unsigned long size_t;
+            struct tm;
+            extern int printf(const char *format, ...);
+            static inline size_t len(const struct tm *t) { return 0; }
+            void (*signal(int sig, void (*handler)(int)))(int);",
+        ))
+        .parse();
+
+        assert_eq!(
+            declarations[0],
+            CDeclaration::Function {
+                name: "printf".to_string(),
+                ty: CFunctionType {
+                    return_type: int(32, true),
+                    parameters: vec![CParameter {
+                        name: Some("format".to_string()),
+                        ty: CType::Pointer(Box::new(int(8, false))),
+                    }],
+                    variadic: true,
+                },
+                is_static: false,
+            }
+        );
+        assert_eq!(
+            declarations[1],
+            CDeclaration::Function {
+                name: "len".to_string(),
+                ty: CFunctionType {
+                    return_type: int(64, false),
+                    parameters: vec![CParameter {
+                        name: Some("t".to_string()),
+                        ty: CType::Pointer(Box::new(CType::Record("struct tm".to_string()))),
+                    }],
+                    variadic: false,
+                },
+                is_static: true,
+            }
+        );
+        let CDeclaration::Function { name, ty, .. } = &declarations[2] else {
+            panic!("{:?}", declarations[2]);
+        };
+        assert_eq!(name, "signal");
+        assert!(matches!(ty.return_type, CType::Pointer(_)));
+        assert_eq!(declarations.len(), 3);
+    }
+
+    #[test]
+    fn integer_widths_follow_the_target() {
+        let widths = IntegerWidths::from_macros([("__SIZEOF_LONG__", "4")]);
+        let declarations = Parser::new(tokenize("long labs(long x);"))
+            .with_integer_widths(widths)
+            .parse();
+        let CDeclaration::Function { ty, .. } = &declarations[0] else {
+            panic!("{:?}", declarations[0]);
+        };
+        assert_eq!(ty.return_type, int(32, true));
+    }
+
+    #[test]
+    fn enumerators() {
+        let declarations = Parser::new(tokenize(
+            "enum e { A, B = 0x10, C, D = C << 1, E = 0x7fffffff, F };",
+        ))
+        .parse();
+        let values: Vec<_> = declarations
+            .iter()
+            .map(|declaration| match declaration {
+                CDeclaration::Constant { value, .. } => value.clone().map(|value| value.value),
+                declaration => panic!("{:?}", declaration),
+            })
+            .collect();
+        assert_eq!(
+            values,
+            [
+                Ok(0),
+                Ok(16),
+                Ok(17),
+                Ok(34),
+                Ok(0x7fffffff),
+                Err("overflows".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn literal_types() {
+        assert_eq!(evaluate(&[("A", "42")], "A"), value(42, 32, true));
+        assert_eq!(evaluate(&[("A", "42u")], "A"), value(42, 32, false));
+        assert_eq!(evaluate(&[("A", "42L")], "A"), value(42, 64, true));
+        assert_eq!(evaluate(&[("A", "42ull")], "A"), value(42, 64, false));
+        // Decimal literals only get signed types, but octal and hexadecimal
+        // ones get unsigned ones too.
+        assert_eq!(
+            evaluate(&[("A", "4294967295")], "A"),
+            value(4294967295, 64, true)
+        );
+        assert_eq!(
+            evaluate(&[("A", "0xFFFFFFFF")], "A"),
+            value(0xFFFFFFFF, 32, false)
+        );
+        assert_eq!(evaluate(&[("A", "0777")], "A"), value(0o777, 32, true));
+        assert_eq!(evaluate(&[("A", "'\\n'")], "A"), value(10, 32, true));
+    }
+
+    #[test]
+    fn arithmetic_conversions() {
+        assert_eq!(evaluate(&[("A", "-1U")], "A"), value(4294967295, 32, false));
+        assert_eq!(evaluate(&[("A", "~0U")], "A"), value(4294967295, 32, false));
+        assert_eq!(evaluate(&[("A", "(-1L)")], "A"), value(-1, 64, true));
+        // `-1` is converted to `unsigned int`, so is not less than `0U`.
+        assert_eq!(evaluate(&[("A", "-1 < 0U")], "A"), value(0, 32, true));
+        assert_eq!(evaluate(&[("A", "-1 < 0UL")], "A"), value(0, 32, true));
+        assert_eq!(evaluate(&[("A", "-1L < 0U")], "A"), value(1, 32, true));
+        assert_eq!(evaluate(&[("A", "1U + 1L")], "A"), value(2, 64, true));
+        assert_eq!(
+            evaluate(&[("A", "(unsigned char)300 + 1")], "A"),
+            value(45, 32, true)
+        );
+        assert_eq!(
+            evaluate(&[("A", "1 << 31")], "A"),
+            value(-2147483648, 32, true)
+        );
+        assert_eq!(evaluate(&[("A", "1 ? 1 : 2UL")], "A"), value(1, 64, false));
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        assert_eq!(
+            evaluate(&[("A", "2147483647 + 1")], "A"),
+            Err("overflows".to_string())
+        );
+        assert_eq!(
+            evaluate(&[("A", "1 / 0")], "A"),
+            Err("divides by zero".to_string())
+        );
+        assert_eq!(
+            evaluate(&[("A", "1 << 32")], "A"),
+            Err("overflows".to_string())
+        );
+        assert_eq!(evaluate(&[("A", "x")], "A"), Err("uses `x`".to_string()));
+    }
+
+    #[test]
+    fn macros_expand_as_tokens() {
+        let macros = [("A", "1 + 2"), ("B", "A * 3"), ("C", "(B)")];
+        assert_eq!(evaluate(&macros, "B"), value(7, 32, true));
+        assert_eq!(evaluate(&macros, "C"), value(7, 32, true));
+        // A macro is not expanded again within itself.
+        assert_eq!(
+            evaluate(&[("A", "A + 1")], "A"),
+            Err("uses `A`".to_string())
+        );
+        assert_eq!(
+            evaluate(&[("A", "B"), ("B", "A")], "A"),
+            Err("uses `A`".to_string())
+        );
+    }
+}
@@ -1,8 +1,9 @@
 // #![feature(c_str_literals)]
 
 use std::{
+    cmp::Ordering,
     collections::HashMap,
-    ffi::{c_char, CString},
+    ffi::{c_char, CStr, CString},
 };
 
 pub use llvm_sys as llvm;
@@ -10,32 +11,59 @@ pub use llvm_sys as llvm;
 use llvm::{
     analysis::{LLVMVerifierFailureAction, LLVMVerifyFunction, LLVMVerifyModule},
     core::{
-        LLVMAddFunction, LLVMAppendBasicBlockInContext, LLVMBuildAlloca, LLVMBuildBinOp,
-        LLVMBuildLoad2, LLVMBuildRet, LLVMBuildRetVoid, LLVMBuildStore, LLVMConstIntOfString,
-        LLVMConstRealOfString, LLVMContextCreate, LLVMCreateBuilderInContext,
-        LLVMDoubleTypeInContext, LLVMDumpModule, LLVMFloatTypeInContext, LLVMFunctionType,
-        LLVMGetTarget, LLVMInt128TypeInContext, LLVMInt16TypeInContext, LLVMInt32TypeInContext,
-        LLVMInt64TypeInContext, LLVMInt8TypeInContext, LLVMModuleCreateWithNameInContext,
-        LLVMPositionBuilderAtEnd, LLVMSetTarget, LLVMVoidTypeInContext,
+        LLVMAddFunction, LLVMAddIncoming, LLVMAppendBasicBlockInContext, LLVMBuildAlloca,
+        LLVMBuildAnd, LLVMBuildAtomicCmpXchg, LLVMBuildAtomicRMW, LLVMBuildBinOp, LLVMBuildBr,
+        LLVMBuildCall2, LLVMBuildCast, LLVMBuildCondBr, LLVMBuildExtractValue, LLVMBuildFCmp,
+        LLVMBuildFNeg, LLVMBuildGEP2, LLVMBuildGlobalStringPtr, LLVMBuildICmp, LLVMBuildLoad2,
+        LLVMBuildNeg, LLVMBuildNot, LLVMBuildPhi, LLVMBuildRet, LLVMBuildRetVoid, LLVMBuildSelect,
+        LLVMBuildStore, LLVMBuildTrunc, LLVMBuildUnreachable, LLVMBuildZExt, LLVMConstAllOnes,
+        LLVMConstInt, LLVMConstIntOfArbitraryPrecision, LLVMConstNull, LLVMConstPointerNull,
+        LLVMConstReal, LLVMConstRealOfString, LLVMContextCreate, LLVMCreateBuilderInContext,
+        LLVMDisposeBuilder, LLVMDisposeMessage, LLVMDoubleTypeInContext, LLVMDumpModule,
+        LLVMFloatTypeInContext, LLVMFunctionType, LLVMGetBasicBlockParent,
+        LLVMGetBasicBlockTerminator, LLVMGetEntryBasicBlock, LLVMGetFirstInstruction,
+        LLVMGetInsertBlock, LLVMGetIntTypeWidth, LLVMGetIntrinsicDeclaration, LLVMGetNamedFunction,
+        LLVMGetParam, LLVMGetTarget, LLVMGlobalGetValueType, LLVMInt128TypeInContext,
+        LLVMInt16TypeInContext, LLVMInt1TypeInContext, LLVMInt32TypeInContext,
+        LLVMInt64TypeInContext, LLVMInt8TypeInContext, LLVMIntrinsicGetType, LLVMLookupIntrinsicID,
+        LLVMModuleCreateWithNameInContext, LLVMPointerTypeInContext, LLVMPositionBuilderAtEnd,
+        LLVMPositionBuilderBefore, LLVMSetAlignment, LLVMSetLinkage, LLVMSetOrdering,
+        LLVMSetTarget, LLVMSetValueName2, LLVMSetVolatile, LLVMTypeOf, LLVMVoidTypeInContext,
+    },
+    prelude::{
+        LLVMBasicBlockRef, LLVMBuilderRef, LLVMContextRef, LLVMModuleRef, LLVMTypeRef, LLVMValueRef,
     },
-    prelude::{LLVMBuilderRef, LLVMContextRef, LLVMModuleRef, LLVMTypeRef, LLVMValueRef},
     target::{
-        LLVM_InitializeAllAsmParsers, LLVM_InitializeAllAsmPrinters,
-        LLVM_InitializeAllDisassemblers, LLVM_InitializeAllTargetInfos,
-        LLVM_InitializeAllTargetMCs, LLVM_InitializeAllTargets,
+        LLVMDisposeTargetData, LLVMGetModuleDataLayout, LLVMIntPtrTypeInContext,
+        LLVMSetModuleDataLayout, LLVMStoreSizeOfType, LLVM_InitializeAllAsmParsers,
+        LLVM_InitializeAllAsmPrinters, LLVM_InitializeAllDisassemblers,
+        LLVM_InitializeAllTargetInfos, LLVM_InitializeAllTargetMCs, LLVM_InitializeAllTargets,
     },
     target_machine::{
-        LLVMCodeGenOptLevel, LLVMCodeModel, LLVMCreateTargetMachine, LLVMGetDefaultTargetTriple,
-        LLVMGetTargetFromTriple, LLVMRelocMode, LLVMTargetRef,
+        LLVMCodeGenOptLevel, LLVMCodeModel, LLVMCreateTargetDataLayout, LLVMCreateTargetMachine,
+        LLVMGetDefaultTargetTriple, LLVMGetTargetFromTriple, LLVMRelocMode, LLVMTargetMachineRef,
+        LLVMTargetRef,
     },
     transforms::pass_builder::{LLVMCreatePassBuilderOptions, LLVMRunPasses},
-    LLVMOpcode,
+    LLVMAtomicOrdering, LLVMAtomicRMWBinOp, LLVMIntPredicate, LLVMLinkage, LLVMOpcode,
+    LLVMRealPredicate,
 };
 
+use zoid_diagnostics::{codes, Diagnostic};
 use zoid_hlir::{
-    HLIRBinaryOperator, HLIRExpression, HLIRFunction, HLIRLiteral, HLIRProgram, HLIRStatement,
-    HLIRType,
+    HLIRAtomicOrdering, HLIRBinaryOperator, HLIRBindingId, HLIRConstant, HLIRExpression,
+    HLIRFunction, HLIRIntrinsic, HLIRLiteral, HLIRProgram, HLIRPrototype, HLIRStatement, HLIRType,
+    HLIRUnaryOperator, Span,
 };
+use zoid_span::SourceMap;
+
+/// How a load or store accesses memory.
+#[derive(Debug, Clone, Copy)]
+enum MemoryAccess {
+    Plain,
+    Volatile,
+    Atomic(LLVMAtomicOrdering),
+}
 
 #[derive(Debug, Clone)]
 pub struct ZoidCodeGenContext<'source> {
@@ -44,9 +72,27 @@ pub struct ZoidCodeGenContext<'source> {
     pub context: LLVMContextRef,
     pub module: LLVMModuleRef,
     pub builder: LLVMBuilderRef,
+    /// The machine the module is generated for, which gives the module its
+    /// data layout, or `None` if its target is unknown, which is reported.
+    pub target_machine: Option<LLVMTargetMachineRef>,
 
     pub named_types: HashMap<&'source str, LLVMTypeRef>,
-    pub named_values: HashMap<&'source str, LLVMValueRef>,
+    pub functions: HashMap<&'source str, LLVMValueRef>,
+    /// The allocas of the parameters and variables of the function being
+    /// generated.
+    pub variables: HashMap<HLIRBindingId, LLVMValueRef>,
+    /// The `continue` and `break` targets of the enclosing loops, innermost
+    /// last.
+    pub loops: Vec<(LLVMBasicBlockRef, LLVMBasicBlockRef)>,
+    /// Whether `+`, `-`, `*`, negation and signed division trap when they
+    /// overflow rather than wrapping around. Division by zero always traps.
+    pub overflow_checks: bool,
+    /// The source files, for reporting where a failed run-time check is.
+    pub source_map: Option<&'source SourceMap>,
+    /// The function that reports a failed run-time check, once generated.
+    panic_function: Option<LLVMValueRef>,
+
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl<'source> ZoidCodeGenContext<'source> {
@@ -77,27 +123,74 @@ impl<'source> ZoidCodeGenContext<'source> {
             add_type("u128", unsafe { LLVMInt128TypeInContext(context) });
             add_type("f32", unsafe { LLVMFloatTypeInContext(context) });
             add_type("f64", unsafe { LLVMDoubleTypeInContext(context) });
+            add_type("bool", unsafe { LLVMInt1TypeInContext(context) });
+            // Pointers are opaque, so all pointer types share one LLVM type.
+            add_type("ptr", unsafe { LLVMPointerTypeInContext(context, 0) });
         }
 
-        ZoidCodeGenContext {
+        let mut codegen = ZoidCodeGenContext {
             program,
             context,
             module,
             builder,
+            target_machine: None,
             named_types,
-            named_values: HashMap::new(),
+            functions: HashMap::new(),
+            variables: HashMap::new(),
+            loops: Vec::new(),
+            overflow_checks: true,
+            source_map: None,
+            panic_function: None,
+            diagnostics: Vec::new(),
+        };
+
+        // Sizes and alignments, such as those of atomic accesses, come from
+        // the data layout, which is the target's rather than LLVM's default.
+        codegen.target_machine = codegen.create_target_machine();
+        if let Some(tm) = codegen.target_machine {
+            unsafe {
+                let data_layout = LLVMCreateTargetDataLayout(tm);
+                LLVMSetModuleDataLayout(module, data_layout);
+                LLVMDisposeTargetData(data_layout);
+            }
         }
+
+        codegen
     }
 
-    pub fn verify(&self) {
-        unsafe {
-            let msg: *mut *mut c_char = std::ptr::null_mut();
+    /// Sets whether arithmetic that overflows traps. On by default.
+    pub fn with_overflow_checks(mut self, overflow_checks: bool) -> Self {
+        self.overflow_checks = overflow_checks;
+        self
+    }
+
+    /// Includes locations in `source_map` in the messages of failed run-time
+    /// checks.
+    pub fn with_source_map(mut self, source_map: &'source SourceMap) -> Self {
+        self.source_map = Some(source_map);
+        self
+    }
+
+    /// Checks the generated module, recording an error if it is invalid. An
+    /// invalid module is always a bug in the compiler.
+    pub fn verify(&mut self) {
+        let mut msg: *mut c_char = std::ptr::null_mut();
+        let failed = unsafe {
             LLVMVerifyModule(
                 self.module,
-                LLVMVerifierFailureAction::LLVMPrintMessageAction,
-                msg,
+                LLVMVerifierFailureAction::LLVMReturnStatusAction,
+                &mut msg,
             )
         };
+
+        let message = take_llvm_message(msg);
+        if failed != 0 {
+            self.diagnostics.push(
+                Diagnostic::error("internal compiler error: generated an invalid LLVM module")
+                    .with_code(codes::INVALID_MODULE)
+                    .with_note(message.unwrap_or_default().trim_end().to_string()),
+            );
+        }
     }
 
     pub fn dump(&self) {
@@ -107,6 +200,19 @@ impl<'source> ZoidCodeGenContext<'source> {
     }
 
     pub fn optimize(&mut self) {
+        let Some(tm) = self.target_machine else {
+            return;
+        };
+
+        let opts = unsafe { LLVMCreatePassBuilderOptions() };
+        let passes = CString::new("constmerge,instcombine").unwrap();
+
+        let _err = unsafe { LLVMRunPasses(self.module, passes.as_ptr(), tm, opts) };
+    }
+
+    /// Creates the target machine for the module's target triple, reporting
+    /// an error if there is no such target.
+    fn create_target_machine(&mut self) -> Option<LLVMTargetMachineRef> {
         unsafe {
             LLVM_InitializeAllTargets();
             LLVM_InitializeAllTargetInfos();
@@ -117,12 +223,19 @@ impl<'source> ZoidCodeGenContext<'source> {
         };
 
         let triple = unsafe { LLVMGetTarget(self.module) };
-        let target = unsafe {
-            let mut t: LLVMTargetRef = std::ptr::null_mut();
-            let err: *mut *mut c_char = std::ptr::null_mut();
-            LLVMGetTargetFromTriple(triple, &mut t, err);
-            t
-        };
+        let mut target: LLVMTargetRef = std::ptr::null_mut();
+        let mut err: *mut c_char = std::ptr::null_mut();
+        let failed = unsafe { LLVMGetTargetFromTriple(triple, &mut target, &mut err) };
+        let err = take_llvm_message(err);
+        if failed != 0 {
+            let triple = unsafe { CStr::from_ptr(triple) }.to_string_lossy();
+            self.diagnostics.push(
+                Diagnostic::error(format!("could not find a target for `{}`", triple))
+                    .with_code(codes::UNKNOWN_TARGET)
+                    .with_note(err.unwrap_or_default()),
+            );
+            return None;
+        }
 
         let tm = unsafe {
             LLVMCreateTargetMachine(
@@ -135,20 +248,12 @@ impl<'source> ZoidCodeGenContext<'source> {
                 LLVMCodeModel::LLVMCodeModelDefault,
             )
         };
-
-        let opts = unsafe { LLVMCreatePassBuilderOptions() };
-        let passes = CString::new("constmerge,instcombine").unwrap();
-
-        let _err = unsafe { LLVMRunPasses(self.module, passes.as_ptr(), tm, opts) };
+        Some(tm)
     }
 
     pub fn codegen(&mut self) {
-        for _global in &self.program.globals {
-            todo!();
-        }
-
-        for (name, types) in &self.program.prototypes.clone() {
-            self.codegen_prototype(&(name, types.clone()));
+        for (name, prototype) in &self.program.prototypes.clone() {
+            self.codegen_prototype(name, prototype);
         }
 
         for function in &self.program.functions.clone() {
@@ -171,36 +276,49 @@ impl<'source> ZoidCodeGenContext<'source> {
             HLIRType::U128 => self.named_types["u128"],
             HLIRType::F32 => self.named_types["f32"],
             HLIRType::F64 => self.named_types["f64"],
+            HLIRType::Bool => self.named_types["bool"],
+            HLIRType::Pointer(_) => self.named_types["ptr"],
             HLIRType::Var(_) => panic!("Type variable found in codegen"),
         }
     }
 
-    fn codegen_prototype(&mut self, prototype: &(&'source str, (Vec<HLIRType>, HLIRType))) {
-        let name = CString::new(prototype.0).unwrap();
+    /// The type used for values of type `ty` stored in memory. This differs
+    /// from `codegen_type` for `bool`, which is an `i1` in registers but a
+    /// whole byte in memory, as in C.
+    fn codegen_memory_type(&mut self, ty: &HLIRType) -> LLVMTypeRef {
+        match ty {
+            HLIRType::Bool => self.named_types["i8"],
+            ty => self.codegen_type(ty),
+        }
+    }
+
+    fn codegen_prototype(&mut self, name: &'source str, prototype: &HLIRPrototype) {
+        let c_name = CString::new(name).unwrap();
 
         let mut param_types = Vec::new();
-        for param_ty in &prototype.1 .0 {
+        for param_ty in &prototype.parameters {
             param_types.push(self.codegen_type(param_ty));
         }
 
-        let ret_ty = self.codegen_type(&prototype.1 .1);
+        let ret_ty = self.codegen_type(&prototype.return_type);
 
         let func_ty = unsafe {
             LLVMFunctionType(
                 ret_ty,
                 param_types.as_mut_ptr(),
                 param_types.len() as u32,
-                0,
+                prototype.variadic as i32,
             )
         };
 
-        let func = unsafe { LLVMAddFunction(self.module, name.as_ptr(), func_ty) };
+        let func = unsafe { LLVMAddFunction(self.module, c_name.as_ptr(), func_ty) };
 
-        self.named_values.insert(prototype.0, func);
+        self.functions.insert(name, func);
     }
 
     fn codegen_function(&mut self, function: &HLIRFunction<'source>) {
-        let func = *self.named_values.get(function.name).unwrap();
+        let func = self.functions[function.name];
+        self.variables.clear();
 
         let entry = unsafe { LLVMAppendBasicBlockInContext(self.context, func, c"entry".as_ptr()) };
 
@@ -208,29 +326,216 @@ impl<'source> ZoidCodeGenContext<'source> {
             LLVMPositionBuilderAtEnd(self.builder, entry);
         }
 
-        for statement in &function.body {
+        // Parameters are spilled to allocas so that they can be treated like
+        // any other local variable.
+        for (i, parameter) in function.parameters.iter().enumerate() {
+            let name = CString::new(parameter.name).unwrap();
+            let value = unsafe {
+                let value = LLVMGetParam(func, i as u32);
+                LLVMSetValueName2(value, name.as_ptr(), parameter.name.len());
+                value
+            };
+            let var = self.build_entry_alloca(&parameter.ty, c"param");
+            self.build_store(&parameter.ty, value, var);
+
+            self.variables.insert(parameter.binding, var);
+        }
+
+        self.codegen_block(&function.body);
+
+        // Lowering checks that functions returning a value always do, so the
+        // end of such a function can't be reached.
+        if !self.is_terminated() {
+            unsafe {
+                if function.return_type == HLIRType::Void {
+                    LLVMBuildRetVoid(self.builder);
+                } else {
+                    LLVMBuildUnreachable(self.builder);
+                }
+            }
+        }
+
+        let failed =
+            unsafe { LLVMVerifyFunction(func, LLVMVerifierFailureAction::LLVMReturnStatusAction) };
+        if failed != 0 {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "internal compiler error: generated invalid code for `{}`",
+                    function.name
+                ))
+                .with_code(codes::INVALID_MODULE)
+                .with_primary(function.span, ""),
+            );
+        }
+    }
+
+    fn codegen_block(&mut self, block: &[HLIRStatement<'source>]) {
+        for statement in block {
+            // Anything after a `return` is unreachable, and can't be added to
+            // a block that already has a terminator.
+            if self.is_terminated() {
+                return;
+            }
             self.codegen_statement(statement);
         }
+    }
+
+    /// Builds an alloca at the start of the current function's entry block,
+    /// so that a variable declared in a loop doesn't take more stack space on
+    /// every iteration.
+    fn build_entry_alloca(&mut self, ty: &HLIRType, name: &CStr) -> LLVMValueRef {
+        let ty = self.codegen_memory_type(ty);
+        unsafe {
+            let func = LLVMGetBasicBlockParent(LLVMGetInsertBlock(self.builder));
+            let entry = LLVMGetEntryBasicBlock(func);
+            let first = LLVMGetFirstInstruction(entry);
+
+            let builder = LLVMCreateBuilderInContext(self.context);
+            if first.is_null() {
+                LLVMPositionBuilderAtEnd(builder, entry);
+            } else {
+                LLVMPositionBuilderBefore(builder, first);
+            }
+            let var = LLVMBuildAlloca(builder, ty, name.as_ptr());
+            LLVMDisposeBuilder(builder);
+
+            var
+        }
+    }
+
+    /// Stores a value of type `ty` to memory allocated for that type.
+    fn build_store(&mut self, ty: &HLIRType, value: LLVMValueRef, ptr: LLVMValueRef) {
+        self.build_store_with(ty, value, ptr, MemoryAccess::Plain);
+    }
+
+    fn build_store_with(
+        &mut self,
+        ty: &HLIRType,
+        value: LLVMValueRef,
+        ptr: LLVMValueRef,
+        access: MemoryAccess,
+    ) -> LLVMValueRef {
+        unsafe {
+            let value = if *ty == HLIRType::Bool {
+                LLVMBuildZExt(
+                    self.builder,
+                    value,
+                    self.codegen_memory_type(ty),
+                    c"bool_to_mem".as_ptr(),
+                )
+            } else {
+                value
+            };
+            let store = LLVMBuildStore(self.builder, value, ptr);
+            self.set_memory_access(store, ty, access);
+            store
+        }
+    }
+
+    /// Loads a value of type `ty` from memory allocated for that type.
+    fn build_load(&mut self, ty: &HLIRType, ptr: LLVMValueRef) -> LLVMValueRef {
+        self.build_load_with(ty, ptr, MemoryAccess::Plain)
+    }
+
+    fn build_load_with(
+        &mut self,
+        ty: &HLIRType,
+        ptr: LLVMValueRef,
+        access: MemoryAccess,
+    ) -> LLVMValueRef {
+        unsafe {
+            let value = LLVMBuildLoad2(
+                self.builder,
+                self.codegen_memory_type(ty),
+                ptr,
+                c"var_expr".as_ptr(),
+            );
+            self.set_memory_access(value, ty, access);
+            if *ty == HLIRType::Bool {
+                LLVMBuildTrunc(
+                    self.builder,
+                    value,
+                    self.codegen_type(ty),
+                    c"mem_to_bool".as_ptr(),
+                )
+            } else {
+                value
+            }
+        }
+    }
 
-        unsafe { LLVMVerifyFunction(func, LLVMVerifierFailureAction::LLVMPrintMessageAction) };
+    /// Marks a load or store of a value of type `ty` as volatile or atomic.
+    /// Atomic accesses must be aligned to their size, which the module's
+    /// default alignment does not guarantee for 64-bit values.
+    fn set_memory_access(
+        &mut self,
+        instruction: LLVMValueRef,
+        ty: &HLIRType,
+        access: MemoryAccess,
+    ) {
+        match access {
+            MemoryAccess::Plain => (),
+            MemoryAccess::Volatile => unsafe { LLVMSetVolatile(instruction, 1) },
+            MemoryAccess::Atomic(ordering) => unsafe {
+                LLVMSetOrdering(instruction, ordering);
+                if let Some(size) = self.size_of(ty) {
+                    LLVMSetAlignment(instruction, size);
+                }
+            },
+        }
+    }
+
+    /// The size in bytes of a value of type `ty` in memory on the target, or
+    /// `None` for `void`, which has no values.
+    fn size_of(&mut self, ty: &HLIRType) -> Option<u32> {
+        if *ty == HLIRType::Void {
+            return None;
+        }
+        let ty = self.codegen_memory_type(ty);
+        let size = unsafe { LLVMStoreSizeOfType(LLVMGetModuleDataLayout(self.module), ty) };
+        Some(size as u32)
+    }
+
+    /// Generates `body` into `block`, then jumps to `next` unless the body
+    /// already ended in a terminator such as a `return`.
+    fn codegen_branch(
+        &mut self,
+        block: LLVMBasicBlockRef,
+        body: &[HLIRStatement<'source>],
+        next: LLVMBasicBlockRef,
+    ) {
+        unsafe { LLVMPositionBuilderAtEnd(self.builder, block) };
+        self.codegen_block(body);
+        if !self.is_terminated() {
+            unsafe { LLVMBuildBr(self.builder, next) };
+        }
+    }
+
+    /// Whether the block being built already ends in a terminator.
+    fn is_terminated(&self) -> bool {
+        unsafe { !LLVMGetBasicBlockTerminator(LLVMGetInsertBlock(self.builder)).is_null() }
+    }
+
+    fn append_block(&mut self, name: &CStr) -> LLVMBasicBlockRef {
+        unsafe {
+            let func = LLVMGetBasicBlockParent(LLVMGetInsertBlock(self.builder));
+            LLVMAppendBasicBlockInContext(self.context, func, name.as_ptr())
+        }
     }
 
     fn codegen_statement(&mut self, statement: &HLIRStatement<'source>) {
         match statement {
-            HLIRStatement::VariableDeclaration { name, ty, value } => {
-                let var = unsafe {
-                    LLVMBuildAlloca(self.builder, self.codegen_type(ty), c"var_decl".as_ptr())
-                };
+            HLIRStatement::VariableDeclaration {
+                binding, ty, value, ..
+            } => {
+                let var = self.build_entry_alloca(ty, c"var_decl");
 
-                self.named_values.insert(name, var);
+                self.variables.insert(*binding, var);
 
                 let value = self.codegen_expression(value);
-
-                unsafe {
-                    LLVMBuildStore(self.builder, value, var);
-                }
+                self.build_store(ty, value, var);
             }
-            HLIRStatement::Return(value) => match value {
+            HLIRStatement::Return(value, _) => match value {
                 Some(value) => {
                     let expr = self.codegen_expression(value);
 
@@ -240,33 +545,323 @@ impl<'source> ZoidCodeGenContext<'source> {
                     LLVMBuildRetVoid(self.builder);
                 },
             },
+            HLIRStatement::Expression(expression, _) => {
+                self.codegen_expression(expression);
+            }
+            HLIRStatement::Assignment {
+                target,
+                op,
+                value,
+                span,
+            } => {
+                let ty = target.ty();
+                let value_ty = value.ty();
+                let ptr = self.codegen_place(target);
+                let mut value = self.codegen_expression(value);
+                if let Some(op) = op {
+                    let current = self.build_load(&ty, ptr);
+                    value = self.build_arithmetic(*op, current, value, &ty, &value_ty, *span);
+                }
+                self.build_store(&ty, value, ptr);
+            }
+            HLIRStatement::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let condition = self.codegen_expression(condition);
+
+                let then_block = self.append_block(c"if.then");
+                let else_block = else_branch.as_ref().map(|_| self.append_block(c"if.else"));
+                let end_block = self.append_block(c"if.end");
+
+                unsafe {
+                    LLVMBuildCondBr(
+                        self.builder,
+                        condition,
+                        then_block,
+                        else_block.unwrap_or(end_block),
+                    );
+                }
+
+                self.codegen_branch(then_block, then_branch, end_block);
+                if let (Some(else_block), Some(else_branch)) = (else_block, else_branch) {
+                    self.codegen_branch(else_block, else_branch, end_block);
+                }
+
+                unsafe { LLVMPositionBuilderAtEnd(self.builder, end_block) };
+            }
+            HLIRStatement::While {
+                condition, body, ..
+            } => {
+                let cond_block = self.append_block(c"while.cond");
+                let body_block = self.append_block(c"while.body");
+                let end_block = self.append_block(c"while.end");
+
+                unsafe {
+                    LLVMBuildBr(self.builder, cond_block);
+                    LLVMPositionBuilderAtEnd(self.builder, cond_block);
+                }
+                let condition = self.codegen_expression(condition);
+                unsafe { LLVMBuildCondBr(self.builder, condition, body_block, end_block) };
+
+                self.loops.push((cond_block, end_block));
+                self.codegen_branch(body_block, body, cond_block);
+                self.loops.pop();
+
+                unsafe { LLVMPositionBuilderAtEnd(self.builder, end_block) };
+            }
+            HLIRStatement::Break(_) => {
+                let (_, break_block) = *self.loops.last().unwrap();
+                unsafe { LLVMBuildBr(self.builder, break_block) };
+            }
+            HLIRStatement::Continue(_) => {
+                let (continue_block, _) = *self.loops.last().unwrap();
+                unsafe { LLVMBuildBr(self.builder, continue_block) };
+            }
         }
     }
 
     fn codegen_expression(&mut self, expression: &HLIRExpression<'source>) -> LLVMValueRef {
         match expression {
-            HLIRExpression::Literal(literal, _) => self.codegen_literal(literal),
-            HLIRExpression::Variable(name, ty) => {
-                let ty = self.codegen_type(ty);
-                let var = self.named_values.get(name).unwrap();
-                unsafe { LLVMBuildLoad2(self.builder, ty, *var, c"var_expr".as_ptr()) }
+            HLIRExpression::Literal(literal, _, _) => self.codegen_literal(literal),
+            HLIRExpression::Variable(_, binding, ty, _) => {
+                let var = self.variables[binding];
+                self.build_load(ty, var)
+            }
+            HLIRExpression::Constant(name, ty, _) => {
+                let (_, value) = self.program.globals[name];
+                self.codegen_constant(value, ty)
             }
-            HLIRExpression::BinaryOperation { lhs, op, rhs, ty } => {
-                self.codegen_binary_operation(lhs, *op, rhs, ty)
+            HLIRExpression::BinaryOperation {
+                lhs,
+                op,
+                rhs,
+                ty,
+                span,
+            } => self.codegen_binary_operation(lhs, *op, rhs, ty, *span),
+            HLIRExpression::UnaryOperation {
+                op, operand, span, ..
+            } => self.codegen_unary_operation(*op, operand, *span),
+            HLIRExpression::Call {
+                callee,
+                arguments,
+                ty,
+                ..
+            } => {
+                let func = self.functions[callee];
+                let fixed = self.program.prototypes[callee].parameters.len();
+                let mut args: Vec<LLVMValueRef> = arguments
+                    .iter()
+                    .enumerate()
+                    .map(|(i, argument)| {
+                        let value = self.codegen_expression(argument);
+                        if i < fixed {
+                            value
+                        } else {
+                            self.promote_variadic_argument(value, &argument.ty())
+                        }
+                    })
+                    .collect();
+                // LLVM does not allow naming the result of a `void` call.
+                let name = if *ty == HLIRType::Void { c"" } else { c"call" };
+                unsafe {
+                    LLVMBuildCall2(
+                        self.builder,
+                        LLVMGlobalGetValueType(func),
+                        func,
+                        args.as_mut_ptr(),
+                        args.len() as u32,
+                        name.as_ptr(),
+                    )
+                }
+            }
+            HLIRExpression::Intrinsic {
+                intrinsic,
+                arguments,
+                ..
+            } => self.codegen_intrinsic(*intrinsic, arguments),
+            HLIRExpression::Cast { value, ty, .. } => self.codegen_cast(value, ty),
+            HLIRExpression::AddressOf { operand, .. } => self.codegen_place(operand),
+            HLIRExpression::Dereference { operand, ty, .. } => {
+                let ptr = self.codegen_expression(operand);
+                self.build_load(ty, ptr)
             }
         }
     }
 
+    /// Applies C's default argument promotions to an argument passed through
+    /// `...`: `f32` becomes `f64`, and integers smaller than `int`, including
+    /// `bool`, become `i32`.
+    fn promote_variadic_argument(&mut self, value: LLVMValueRef, ty: &HLIRType) -> LLVMValueRef {
+        let (opcode, promoted_ty) = match ty {
+            HLIRType::F32 => (LLVMOpcode::LLVMFPExt, self.named_types["f64"]),
+            HLIRType::I8 | HLIRType::I16 => (LLVMOpcode::LLVMSExt, self.named_types["i32"]),
+            HLIRType::U8 | HLIRType::U16 | HLIRType::Bool => {
+                (LLVMOpcode::LLVMZExt, self.named_types["i32"])
+            }
+            _ => return value,
+        };
+        unsafe {
+            LLVMBuildCast(
+                self.builder,
+                opcode,
+                value,
+                promoted_ty,
+                c"promoted".as_ptr(),
+            )
+        }
+    }
+
+    /// Generates a builtin memory operation inline. The first argument is the
+    /// pointer to access.
+    fn codegen_intrinsic(
+        &mut self,
+        intrinsic: HLIRIntrinsic,
+        arguments: &[HLIRExpression<'source>],
+    ) -> LLVMValueRef {
+        let ty = arguments[0]
+            .ty()
+            .pointee()
+            .expect("intrinsics access memory through a pointer");
+        let arguments: Vec<_> = arguments
+            .iter()
+            .map(|argument| self.codegen_expression(argument))
+            .collect();
+        let ptr = arguments[0];
+
+        match intrinsic {
+            HLIRIntrinsic::VolatileLoad => self.build_load_with(&ty, ptr, MemoryAccess::Volatile),
+            HLIRIntrinsic::VolatileStore => {
+                self.build_store_with(&ty, arguments[1], ptr, MemoryAccess::Volatile)
+            }
+            HLIRIntrinsic::AtomicLoad(ordering) => {
+                let access = MemoryAccess::Atomic(Self::codegen_atomic_ordering(ordering));
+                self.build_load_with(&ty, ptr, access)
+            }
+            HLIRIntrinsic::AtomicStore(ordering) => {
+                let access = MemoryAccess::Atomic(Self::codegen_atomic_ordering(ordering));
+                self.build_store_with(&ty, arguments[1], ptr, access)
+            }
+            // `cmpxchg` gives the previous value and whether the exchange
+            // happened. Only the value is kept, as the exchange happened
+            // exactly when it equals the expected value.
+            HLIRIntrinsic::AtomicCompareExchange { success, failure } => unsafe {
+                let pair = LLVMBuildAtomicCmpXchg(
+                    self.builder,
+                    ptr,
+                    arguments[1],
+                    arguments[2],
+                    Self::codegen_atomic_ordering(success),
+                    Self::codegen_atomic_ordering(failure),
+                    0,
+                );
+                LLVMBuildExtractValue(self.builder, pair, 0, c"cmpxchg.old".as_ptr())
+            },
+            HLIRIntrinsic::AtomicFetchAdd(ordering) => unsafe {
+                LLVMBuildAtomicRMW(
+                    self.builder,
+                    LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpAdd,
+                    ptr,
+                    arguments[1],
+                    Self::codegen_atomic_ordering(ordering),
+                    0,
+                )
+            },
+        }
+    }
+
+    fn codegen_atomic_ordering(ordering: HLIRAtomicOrdering) -> LLVMAtomicOrdering {
+        match ordering {
+            HLIRAtomicOrdering::Relaxed => LLVMAtomicOrdering::LLVMAtomicOrderingMonotonic,
+            HLIRAtomicOrdering::Acquire => LLVMAtomicOrdering::LLVMAtomicOrderingAcquire,
+            HLIRAtomicOrdering::Release => LLVMAtomicOrdering::LLVMAtomicOrderingRelease,
+            HLIRAtomicOrdering::AcqRel => LLVMAtomicOrdering::LLVMAtomicOrderingAcquireRelease,
+            HLIRAtomicOrdering::SeqCst => {
+                LLVMAtomicOrdering::LLVMAtomicOrderingSequentiallyConsistent
+            }
+        }
+    }
+
+    /// Converts a value to `to`, choosing the conversion from the value's
+    /// type. Lowering only allows casts between numeric types, from `bool`
+    /// to integer types, and between pointers and pointers or integers.
+    fn codegen_cast(&mut self, value: &HLIRExpression<'source>, to: &HLIRType) -> LLVMValueRef {
+        let from = value.ty();
+        let value = self.codegen_expression(value);
+
+        let opcode = match (from.integer_bits(), to.integer_bits()) {
+            // Signed and unsigned integers of the same width are the same
+            // LLVM type, as are all pointers.
+            _ if from == *to => return value,
+            _ if from.pointee().is_some() && to.pointee().is_some() => return value,
+            _ if from.pointee().is_some() => LLVMOpcode::LLVMPtrToInt,
+            _ if to.pointee().is_some() => LLVMOpcode::LLVMIntToPtr,
+            (Some(from_bits), Some(to_bits)) => match from_bits.cmp(&to_bits) {
+                Ordering::Greater => LLVMOpcode::LLVMTrunc,
+                Ordering::Less if from.is_signed_integer() => LLVMOpcode::LLVMSExt,
+                Ordering::Less => LLVMOpcode::LLVMZExt,
+                Ordering::Equal => return value,
+            },
+            (Some(_), None) if from.is_signed_integer() => LLVMOpcode::LLVMSIToFP,
+            (Some(_), None) => LLVMOpcode::LLVMUIToFP,
+            (None, Some(_)) if from == HLIRType::Bool => LLVMOpcode::LLVMZExt,
+            (None, Some(_)) if to.is_signed_integer() => LLVMOpcode::LLVMFPToSI,
+            (None, Some(_)) => LLVMOpcode::LLVMFPToUI,
+            (None, None) if *to == HLIRType::F64 => LLVMOpcode::LLVMFPExt,
+            (None, None) => LLVMOpcode::LLVMFPTrunc,
+        };
+
+        unsafe {
+            LLVMBuildCast(
+                self.builder,
+                opcode,
+                value,
+                self.codegen_type(to),
+                c"cast".as_ptr(),
+            )
+        }
+    }
+
+    /// Generates the address of an expression that can be assigned to.
+    fn codegen_place(&mut self, expression: &HLIRExpression<'source>) -> LLVMValueRef {
+        match expression {
+            HLIRExpression::Variable(_, binding, _, _) => self.variables[binding],
+            HLIRExpression::Dereference { operand, .. } => self.codegen_expression(operand),
+            _ => unreachable!("lowering only allows assigning to places"),
+        }
+    }
+
     fn codegen_literal(&mut self, literal: &HLIRLiteral<'source>) -> LLVMValueRef {
         match literal {
             HLIRLiteral::Integer(value, ty) => unsafe {
-                let value = CString::new(*value).unwrap();
-                LLVMConstIntOfString(self.codegen_type(ty), value.as_ptr(), 10)
+                let words = [*value as u64, (*value >> 64) as u64];
+                LLVMConstIntOfArbitraryPrecision(self.codegen_type(ty), 2, words.as_ptr())
+            },
+            HLIRLiteral::Bool(value) => unsafe {
+                LLVMConstInt(self.codegen_type(&HLIRType::Bool), *value as u64, 0)
             },
             HLIRLiteral::Float(value, ty) => unsafe {
-                let value = CString::new(*value).unwrap();
+                let value = CString::new(value.replace('_', "")).unwrap();
                 LLVMConstRealOfString(self.codegen_type(ty), value.as_ptr())
             },
+            HLIRLiteral::Null => unsafe { LLVMConstPointerNull(self.named_types["ptr"]) },
+        }
+    }
+
+    /// Generates the value of a `const`, which is inlined wherever it is used.
+    fn codegen_constant(&mut self, value: HLIRConstant, ty: &HLIRType) -> LLVMValueRef {
+        match value {
+            HLIRConstant::Integer(value) => unsafe {
+                let words = [value as u64, (value >> 64) as u64];
+                LLVMConstIntOfArbitraryPrecision(self.codegen_type(ty), 2, words.as_ptr())
+            },
+            HLIRConstant::Float(value) => unsafe { LLVMConstReal(self.codegen_type(ty), value) },
+            HLIRConstant::Bool(value) => unsafe {
+                LLVMConstInt(self.codegen_type(&HLIRType::Bool), value as u64, 0)
+            },
+            HLIRConstant::Null => unsafe { LLVMConstPointerNull(self.named_types["ptr"]) },
         }
     }
 
@@ -276,10 +871,38 @@ impl<'source> ZoidCodeGenContext<'source> {
         op: HLIRBinaryOperator,
         rhs: &HLIRExpression<'source>,
         ty: &HLIRType,
+        span: Span,
     ) -> LLVMValueRef {
+        if op.is_logical() {
+            return self.codegen_logical_operation(lhs, op, rhs);
+        }
+        if op.is_comparison() {
+            return self.codegen_comparison(lhs, op, rhs);
+        }
+
+        let rhs_ty = rhs.ty();
         let lhs = self.codegen_expression(lhs);
         let rhs = self.codegen_expression(rhs);
 
+        self.build_arithmetic(op, lhs, rhs, ty, &rhs_ty, span)
+    }
+
+    /// Builds an arithmetic operation on a value of type `ty` and one of type
+    /// `rhs_ty`. The types are the same except for shifts and for offsetting
+    /// a pointer. `span` is the operation, for reporting failed checks.
+    fn build_arithmetic(
+        &mut self,
+        op: HLIRBinaryOperator,
+        lhs: LLVMValueRef,
+        rhs: LLVMValueRef,
+        ty: &HLIRType,
+        rhs_ty: &HLIRType,
+        span: Span,
+    ) -> LLVMValueRef {
+        if let Some(pointee) = ty.pointee() {
+            return self.build_pointer_offset(op, lhs, rhs, &pointee, rhs_ty);
+        }
+
         let is_signed = match ty {
             HLIRType::I8 | HLIRType::I16 | HLIRType::I32 | HLIRType::I64 | HLIRType::I128 => true,
             HLIRType::U8 | HLIRType::U16 | HLIRType::U32 | HLIRType::U64 | HLIRType::U128 => false,
@@ -287,35 +910,420 @@ impl<'source> ZoidCodeGenContext<'source> {
             _ => panic!("Invalid type for binary operation"),
         };
 
-        let is_float = match ty {
-            HLIRType::F32 | HLIRType::F64 => true,
-            _ => false,
-        };
+        let is_float = matches!(ty, HLIRType::F32 | HLIRType::F64);
+
+        if !is_float {
+            if op.is_saturating() {
+                return self.build_saturating_arithmetic(op, lhs, rhs, ty);
+            }
+            if op.is_overflowing() && self.overflow_checks {
+                return self.build_checked_arithmetic(op, lhs, rhs, ty, span);
+            }
+        }
 
         let opcode = self.codegen_binary_operator(op, is_signed, is_float);
+        let rhs = if op.is_shift() {
+            self.build_shift_amount(rhs, ty)
+        } else if !is_float && matches!(op, HLIRBinaryOperator::Div | HLIRBinaryOperator::Rem) {
+            self.build_divisor_checks(op, lhs, rhs, ty, span)
+        } else {
+            rhs
+        };
 
         unsafe { LLVMBuildBinOp(self.builder, opcode, lhs, rhs, c"binop".as_ptr()) }
     }
 
+    /// Builds `+`, `-` or `*` on integers, trapping if the result overflows.
+    fn build_checked_arithmetic(
+        &mut self,
+        op: HLIRBinaryOperator,
+        lhs: LLVMValueRef,
+        rhs: LLVMValueRef,
+        ty: &HLIRType,
+        span: Span,
+    ) -> LLVMValueRef {
+        let (operation, message) = match op {
+            HLIRBinaryOperator::Add => ("add", "attempt to add with overflow"),
+            HLIRBinaryOperator::Sub => ("sub", "attempt to subtract with overflow"),
+            _ => ("mul", "attempt to multiply with overflow"),
+        };
+        let sign = if ty.is_signed_integer() { "s" } else { "u" };
+        let intrinsic = format!("llvm.{}{}.with.overflow", sign, operation);
+        self.build_overflowing(&intrinsic, lhs, rhs, ty, message, span)
+    }
+
+    /// Calls an `llvm.*.with.overflow` intrinsic, trapping with `message` if
+    /// it overflowed and giving its result otherwise.
+    fn build_overflowing(
+        &mut self,
+        intrinsic: &str,
+        lhs: LLVMValueRef,
+        rhs: LLVMValueRef,
+        ty: &HLIRType,
+        message: &str,
+        span: Span,
+    ) -> LLVMValueRef {
+        let llvm_ty = self.codegen_type(ty);
+        let result = self.build_intrinsic_call(intrinsic, &[llvm_ty], &mut [lhs, rhs]);
+        unsafe {
+            let value = LLVMBuildExtractValue(self.builder, result, 0, c"checked".as_ptr());
+            let overflowed = LLVMBuildExtractValue(self.builder, result, 1, c"overflowed".as_ptr());
+            self.build_check(overflowed, message, span);
+            value
+        }
+    }
+
+    /// Builds a saturating `+|`, `-|` or `*|`. Saturating multiplication is
+    /// fixed point multiplication with no fractional bits.
+    fn build_saturating_arithmetic(
+        &mut self,
+        op: HLIRBinaryOperator,
+        lhs: LLVMValueRef,
+        rhs: LLVMValueRef,
+        ty: &HLIRType,
+    ) -> LLVMValueRef {
+        let llvm_ty = self.codegen_type(ty);
+        let sign = if ty.is_signed_integer() { "s" } else { "u" };
+        match op {
+            HLIRBinaryOperator::SaturatingAdd => self.build_intrinsic_call(
+                &format!("llvm.{}add.sat", sign),
+                &[llvm_ty],
+                &mut [lhs, rhs],
+            ),
+            HLIRBinaryOperator::SaturatingSub => self.build_intrinsic_call(
+                &format!("llvm.{}sub.sat", sign),
+                &[llvm_ty],
+                &mut [lhs, rhs],
+            ),
+            _ => {
+                let scale = unsafe { LLVMConstInt(self.named_types["i32"], 0, 0) };
+                self.build_intrinsic_call(
+                    &format!("llvm.{}mul.fix.sat", sign),
+                    &[llvm_ty],
+                    &mut [lhs, rhs, scale],
+                )
+            }
+        }
+    }
+
+    /// Traps if an integer divisor is zero, and handles the one signed
+    /// division that overflows, `MIN / -1`, which LLVM leaves undefined. With
+    /// overflow checks it traps, and otherwise it divides by 1 instead, which
+    /// gives the wrapped quotient `MIN` and remainder `0`. Returns the
+    /// divisor to use.
+    fn build_divisor_checks(
+        &mut self,
+        op: HLIRBinaryOperator,
+        lhs: LLVMValueRef,
+        rhs: LLVMValueRef,
+        ty: &HLIRType,
+        span: Span,
+    ) -> LLVMValueRef {
+        let llvm_ty = self.codegen_type(ty);
+        let (zero_message, overflow_message) = match op {
+            HLIRBinaryOperator::Div => (
+                "attempt to divide by zero",
+                "attempt to divide with overflow",
+            ),
+            _ => (
+                "attempt to calculate the remainder with a divisor of zero",
+                "attempt to calculate the remainder with overflow",
+            ),
+        };
+
+        unsafe {
+            let zero = LLVMConstNull(llvm_ty);
+            let is_zero = LLVMBuildICmp(
+                self.builder,
+                LLVMIntPredicate::LLVMIntEQ,
+                rhs,
+                zero,
+                c"is_zero".as_ptr(),
+            );
+            self.build_check(is_zero, zero_message, span);
+            if !ty.is_signed_integer() {
+                return rhs;
+            }
+
+            // The minimum is the complement of the maximum.
+            let min = self.codegen_constant(HLIRConstant::Integer(!ty.integer_max().unwrap()), ty);
+            let minus_one = LLVMConstAllOnes(llvm_ty);
+            let is_min = LLVMBuildICmp(
+                self.builder,
+                LLVMIntPredicate::LLVMIntEQ,
+                lhs,
+                min,
+                c"is_min".as_ptr(),
+            );
+            let is_minus_one = LLVMBuildICmp(
+                self.builder,
+                LLVMIntPredicate::LLVMIntEQ,
+                rhs,
+                minus_one,
+                c"is_minus_one".as_ptr(),
+            );
+            let overflows = LLVMBuildAnd(self.builder, is_min, is_minus_one, c"overflows".as_ptr());
+            if self.overflow_checks {
+                self.build_check(overflows, overflow_message, span);
+                rhs
+            } else {
+                let one = LLVMConstInt(llvm_ty, 1, 0);
+                LLVMBuildSelect(self.builder, overflows, one, rhs, c"divisor".as_ptr())
+            }
+        }
+    }
+
+    /// Branches to a block that traps with `message` if `failed` is true, and
+    /// continues in a new block otherwise.
+    fn build_check(&mut self, failed: LLVMValueRef, message: &str, span: Span) {
+        let fail_block = self.append_block(c"check.fail");
+        let ok_block = self.append_block(c"check.ok");
+        unsafe {
+            LLVMBuildCondBr(self.builder, failed, fail_block, ok_block);
+            LLVMPositionBuilderAtEnd(self.builder, fail_block);
+        }
+        self.build_trap(message, span);
+        unsafe { LLVMPositionBuilderAtEnd(self.builder, ok_block) };
+    }
+
+    /// Writes `message` to stderr, prefixed with the location of `span` if
+    /// the source map is known, then aborts the program.
+    fn build_trap(&mut self, message: &str, span: Span) {
+        let message = match self.source_map {
+            Some(source_map) => {
+                let location = source_map.lookup(span);
+                format!(
+                    "{}:{}:{}: {}\n",
+                    source_map.name(span.file),
+                    location.line,
+                    location.column,
+                    message
+                )
+            }
+            None => format!("{}\n", message),
+        };
+
+        let panic = self.panic_function();
+        unsafe {
+            let usize_ty =
+                LLVMIntPtrTypeInContext(self.context, LLVMGetModuleDataLayout(self.module));
+            let text = CString::new(message.clone()).unwrap();
+            let text = LLVMBuildGlobalStringPtr(self.builder, text.as_ptr(), c"panic.msg".as_ptr());
+            let mut args = [text, LLVMConstInt(usize_ty, message.len() as u64, 0)];
+            LLVMBuildCall2(
+                self.builder,
+                LLVMGlobalGetValueType(panic),
+                panic,
+                args.as_mut_ptr(),
+                args.len() as u32,
+                c"".as_ptr(),
+            );
+            LLVMBuildUnreachable(self.builder);
+        }
+    }
+
+    /// Returns `__zoid_panic(message: *u8, len: usize)`, which writes
+    /// `message` to stderr and traps, generating it on first use. It has
+    /// internal linkage and is found through `panic_function` rather than by
+    /// name, so it cannot clash with the program's own functions.
+    fn panic_function(&mut self) -> LLVMValueRef {
+        if let Some(function) = self.panic_function {
+            return function;
+        }
+
+        let void_ty = self.named_types["void"];
+        let i32_ty = self.named_types["i32"];
+        let ptr_ty = self.named_types["ptr"];
+        unsafe {
+            let usize_ty =
+                LLVMIntPtrTypeInContext(self.context, LLVMGetModuleDataLayout(self.module));
+            let mut param_types = [ptr_ty, usize_ty];
+            let function_type = LLVMFunctionType(
+                void_ty,
+                param_types.as_mut_ptr(),
+                param_types.len() as u32,
+                0,
+            );
+            let function = LLVMAddFunction(self.module, c"__zoid_panic".as_ptr(), function_type);
+            LLVMSetLinkage(function, LLVMLinkage::LLVMInternalLinkage);
+            self.panic_function = Some(function);
+
+            let current = LLVMGetInsertBlock(self.builder);
+            let entry = LLVMAppendBasicBlockInContext(self.context, function, c"entry".as_ptr());
+            LLVMPositionBuilderAtEnd(self.builder, entry);
+            if let Some(write) = self.c_write(usize_ty) {
+                let mut args = [
+                    LLVMConstInt(i32_ty, 2, 0),
+                    LLVMGetParam(function, 0),
+                    LLVMGetParam(function, 1),
+                ];
+                LLVMBuildCall2(
+                    self.builder,
+                    LLVMGlobalGetValueType(write),
+                    write,
+                    args.as_mut_ptr(),
+                    args.len() as u32,
+                    c"".as_ptr(),
+                );
+            }
+            self.build_intrinsic_call("llvm.trap", &[], &mut []);
+            LLVMBuildUnreachable(self.builder);
+            LLVMPositionBuilderAtEnd(self.builder, current);
+
+            function
+        }
+    }
+
+    /// Returns C's `write(fd: i32, buf: *u8, count: usize): isize`,
+    /// declaring it if the program does not. Returns `None` if the program
+    /// defines its own `write`, or declares one of another type, as the C
+    /// library's cannot then be called by that name, so a failed check only
+    /// traps.
+    fn c_write(&mut self, usize_ty: LLVMTypeRef) -> Option<LLVMValueRef> {
+        if self
+            .program
+            .functions
+            .iter()
+            .any(|function| function.name == "write")
+        {
+            return None;
+        }
+
+        let i32_ty = self.named_types["i32"];
+        let ptr_ty = self.named_types["ptr"];
+        unsafe {
+            let mut param_types = [i32_ty, ptr_ty, usize_ty];
+            let function_type = LLVMFunctionType(
+                usize_ty,
+                param_types.as_mut_ptr(),
+                param_types.len() as u32,
+                0,
+            );
+            let write = LLVMGetNamedFunction(self.module, c"write".as_ptr());
+            if write.is_null() {
+                return Some(LLVMAddFunction(
+                    self.module,
+                    c"write".as_ptr(),
+                    function_type,
+                ));
+            }
+            // Types are unique within a context, so are compared by address.
+            (LLVMGlobalGetValueType(write) == function_type).then_some(write)
+        }
+    }
+
+    /// Calls the LLVM intrinsic `name`, instantiated for `types` if it is
+    /// overloaded, such as `llvm.sadd.with.overflow` for `i32`.
+    fn build_intrinsic_call(
+        &mut self,
+        name: &str,
+        types: &[LLVMTypeRef],
+        args: &mut [LLVMValueRef],
+    ) -> LLVMValueRef {
+        let mut types = types.to_vec();
+        unsafe {
+            let id = LLVMLookupIntrinsicID(name.as_ptr() as *const c_char, name.len());
+            let function =
+                LLVMGetIntrinsicDeclaration(self.module, id, types.as_mut_ptr(), types.len());
+            let function_type =
+                LLVMIntrinsicGetType(self.context, id, types.as_mut_ptr(), types.len());
+            LLVMBuildCall2(
+                self.builder,
+                function_type,
+                function,
+                args.as_mut_ptr(),
+                args.len() as u32,
+                c"".as_ptr(),
+            )
+        }
+    }
+
+    /// Offsets a pointer to `pointee` by `offset` elements, backwards for
+    /// `-`. The offset is extended to 64 bits according to its signedness.
+    fn build_pointer_offset(
+        &mut self,
+        op: HLIRBinaryOperator,
+        ptr: LLVMValueRef,
+        offset: LLVMValueRef,
+        pointee: &HLIRType,
+        offset_ty: &HLIRType,
+    ) -> LLVMValueRef {
+        let bits = offset_ty
+            .integer_bits()
+            .expect("pointer offsets are integers");
+        let i64_ty = self.named_types["i64"];
+
+        unsafe {
+            let mut offset = match bits.cmp(&64) {
+                Ordering::Greater => {
+                    LLVMBuildTrunc(self.builder, offset, i64_ty, c"offset.trunc".as_ptr())
+                }
+                Ordering::Less => {
+                    let opcode = if offset_ty.is_signed_integer() {
+                        LLVMOpcode::LLVMSExt
+                    } else {
+                        LLVMOpcode::LLVMZExt
+                    };
+                    LLVMBuildCast(self.builder, opcode, offset, i64_ty, c"offset.ext".as_ptr())
+                }
+                Ordering::Equal => offset,
+            };
+            if op == HLIRBinaryOperator::Sub {
+                offset = LLVMBuildNeg(self.builder, offset, c"offset.neg".as_ptr());
+            }
+            let mut indices = [offset];
+            LLVMBuildGEP2(
+                self.builder,
+                self.codegen_memory_type(pointee),
+                ptr,
+                indices.as_mut_ptr(),
+                indices.len() as u32,
+                c"ptr.offset".as_ptr(),
+            )
+        }
+    }
+
+    /// Converts the amount to shift a value of type `ty` by to that type,
+    /// masked to less than its width. Shifting by the full width or more is
+    /// undefined in LLVM, so `x << n` shifts by `n % width` instead.
+    fn build_shift_amount(&mut self, amount: LLVMValueRef, ty: &HLIRType) -> LLVMValueRef {
+        let bits = ty.integer_bits().expect("only integers can be shifted");
+        let llvm_ty = self.codegen_type(ty);
+
+        unsafe {
+            let amount_bits = LLVMGetIntTypeWidth(LLVMTypeOf(amount));
+            let amount = if amount_bits > bits {
+                LLVMBuildTrunc(self.builder, amount, llvm_ty, c"shift.trunc".as_ptr())
+            } else if amount_bits < bits {
+                LLVMBuildZExt(self.builder, amount, llvm_ty, c"shift.zext".as_ptr())
+            } else {
+                amount
+            };
+            let mask = LLVMConstInt(llvm_ty, (bits - 1) as u64, 0);
+            LLVMBuildAnd(self.builder, amount, mask, c"shift.mask".as_ptr())
+        }
+    }
+
     fn codegen_binary_operator(
         &mut self,
         op: HLIRBinaryOperator,
         is_signed: bool,
         is_float: bool,
     ) -> LLVMOpcode {
-        return if is_float {
+        if is_float {
             match op {
                 HLIRBinaryOperator::Add => LLVMOpcode::LLVMFAdd,
                 HLIRBinaryOperator::Sub => LLVMOpcode::LLVMFSub,
                 HLIRBinaryOperator::Mul => LLVMOpcode::LLVMFMul,
                 HLIRBinaryOperator::Div => LLVMOpcode::LLVMFDiv,
-                _ => panic!("Invalid binary operator for floating point"),
+                HLIRBinaryOperator::Rem => LLVMOpcode::LLVMFRem,
+                _ => unreachable!("{:?} is not an arithmetic operator", op),
             }
         } else {
             match op {
-                HLIRBinaryOperator::Add => LLVMOpcode::LLVMAdd,
-                HLIRBinaryOperator::Sub => LLVMOpcode::LLVMSub,
-                HLIRBinaryOperator::Mul => LLVMOpcode::LLVMMul,
+                HLIRBinaryOperator::Add | HLIRBinaryOperator::WrappingAdd => LLVMOpcode::LLVMAdd,
+                HLIRBinaryOperator::Sub | HLIRBinaryOperator::WrappingSub => LLVMOpcode::LLVMSub,
+                HLIRBinaryOperator::Mul | HLIRBinaryOperator::WrappingMul => LLVMOpcode::LLVMMul,
                 HLIRBinaryOperator::Div => {
                     if is_signed {
                         LLVMOpcode::LLVMSDiv
@@ -330,7 +1338,230 @@ impl<'source> ZoidCodeGenContext<'source> {
                         LLVMOpcode::LLVMURem
                     }
                 }
+                HLIRBinaryOperator::BitAnd => LLVMOpcode::LLVMAnd,
+                HLIRBinaryOperator::BitOr => LLVMOpcode::LLVMOr,
+                HLIRBinaryOperator::BitXor => LLVMOpcode::LLVMXor,
+                HLIRBinaryOperator::Shl => LLVMOpcode::LLVMShl,
+                HLIRBinaryOperator::Shr => {
+                    if is_signed {
+                        LLVMOpcode::LLVMAShr
+                    } else {
+                        LLVMOpcode::LLVMLShr
+                    }
+                }
+                _ => unreachable!("{:?} is not an arithmetic operator", op),
             }
+        }
+    }
+
+    fn codegen_comparison(
+        &mut self,
+        lhs: &HLIRExpression<'source>,
+        op: HLIRBinaryOperator,
+        rhs: &HLIRExpression<'source>,
+    ) -> LLVMValueRef {
+        let operand_ty = lhs.ty();
+        let lhs = self.codegen_expression(lhs);
+        let rhs = self.codegen_expression(rhs);
+
+        if matches!(operand_ty, HLIRType::F32 | HLIRType::F64) {
+            // Ordered comparisons are false if either operand is NaN, except
+            // for `!=` which is true.
+            let predicate = match op {
+                HLIRBinaryOperator::Eq => LLVMRealPredicate::LLVMRealOEQ,
+                HLIRBinaryOperator::Ne => LLVMRealPredicate::LLVMRealUNE,
+                HLIRBinaryOperator::Lt => LLVMRealPredicate::LLVMRealOLT,
+                HLIRBinaryOperator::Le => LLVMRealPredicate::LLVMRealOLE,
+                HLIRBinaryOperator::Gt => LLVMRealPredicate::LLVMRealOGT,
+                HLIRBinaryOperator::Ge => LLVMRealPredicate::LLVMRealOGE,
+                _ => unreachable!("{:?} is not a comparison operator", op),
+            };
+            return unsafe { LLVMBuildFCmp(self.builder, predicate, lhs, rhs, c"cmp".as_ptr()) };
+        }
+
+        let is_signed = operand_ty.is_signed_integer();
+        let predicate = match op {
+            HLIRBinaryOperator::Eq => LLVMIntPredicate::LLVMIntEQ,
+            HLIRBinaryOperator::Ne => LLVMIntPredicate::LLVMIntNE,
+            HLIRBinaryOperator::Lt if is_signed => LLVMIntPredicate::LLVMIntSLT,
+            HLIRBinaryOperator::Lt => LLVMIntPredicate::LLVMIntULT,
+            HLIRBinaryOperator::Le if is_signed => LLVMIntPredicate::LLVMIntSLE,
+            HLIRBinaryOperator::Le => LLVMIntPredicate::LLVMIntULE,
+            HLIRBinaryOperator::Gt if is_signed => LLVMIntPredicate::LLVMIntSGT,
+            HLIRBinaryOperator::Gt => LLVMIntPredicate::LLVMIntUGT,
+            HLIRBinaryOperator::Ge if is_signed => LLVMIntPredicate::LLVMIntSGE,
+            HLIRBinaryOperator::Ge => LLVMIntPredicate::LLVMIntUGE,
+            _ => unreachable!("{:?} is not a comparison operator", op),
         };
+        unsafe { LLVMBuildICmp(self.builder, predicate, lhs, rhs, c"cmp".as_ptr()) }
+    }
+
+    /// Generates `&&` and `||`, which only evaluate their right operand if
+    /// the left one doesn't already decide the result.
+    fn codegen_logical_operation(
+        &mut self,
+        lhs: &HLIRExpression<'source>,
+        op: HLIRBinaryOperator,
+        rhs: &HLIRExpression<'source>,
+    ) -> LLVMValueRef {
+        let is_and = op == HLIRBinaryOperator::And;
+
+        let lhs = self.codegen_expression(lhs);
+        let lhs_block = unsafe { LLVMGetInsertBlock(self.builder) };
+
+        let (rhs_block, end_block) = if is_and {
+            (self.append_block(c"and.rhs"), self.append_block(c"and.end"))
+        } else {
+            (self.append_block(c"or.rhs"), self.append_block(c"or.end"))
+        };
+
+        unsafe {
+            if is_and {
+                LLVMBuildCondBr(self.builder, lhs, rhs_block, end_block);
+            } else {
+                LLVMBuildCondBr(self.builder, lhs, end_block, rhs_block);
+            }
+            LLVMPositionBuilderAtEnd(self.builder, rhs_block);
+        }
+
+        let rhs = self.codegen_expression(rhs);
+        // The right operand may have added blocks of its own.
+        let rhs_end_block = unsafe { LLVMGetInsertBlock(self.builder) };
+
+        unsafe {
+            LLVMBuildBr(self.builder, end_block);
+            LLVMPositionBuilderAtEnd(self.builder, end_block);
+
+            let bool_ty = self.codegen_type(&HLIRType::Bool);
+            let phi = LLVMBuildPhi(self.builder, bool_ty, c"logical".as_ptr());
+            // Skipping the right operand means the result is `false` for `&&`
+            // and `true` for `||`.
+            let short_circuit = LLVMConstInt(bool_ty, if is_and { 0 } else { 1 }, 0);
+            let mut values = [short_circuit, rhs];
+            let mut blocks = [lhs_block, rhs_end_block];
+            LLVMAddIncoming(phi, values.as_mut_ptr(), blocks.as_mut_ptr(), 2);
+
+            phi
+        }
+    }
+
+    fn codegen_unary_operation(
+        &mut self,
+        op: HLIRUnaryOperator,
+        operand: &HLIRExpression<'source>,
+        span: Span,
+    ) -> LLVMValueRef {
+        let ty = operand.ty();
+        let operand = self.codegen_expression(operand);
+
+        match op {
+            HLIRUnaryOperator::Neg if ty.is_float() => unsafe {
+                LLVMBuildFNeg(self.builder, operand, c"neg".as_ptr())
+            },
+            // Only the minimum of a signed type overflows when negated.
+            HLIRUnaryOperator::Neg if self.overflow_checks => {
+                let zero = unsafe { LLVMConstNull(self.codegen_type(&ty)) };
+                self.build_overflowing(
+                    "llvm.ssub.with.overflow",
+                    zero,
+                    operand,
+                    &ty,
+                    "attempt to negate with overflow",
+                    span,
+                )
+            }
+            HLIRUnaryOperator::Neg => unsafe {
+                LLVMBuildNeg(self.builder, operand, c"neg".as_ptr())
+            },
+            HLIRUnaryOperator::Not => unsafe {
+                LLVMBuildNot(self.builder, operand, c"not".as_ptr())
+            },
+            HLIRUnaryOperator::BitNot => unsafe {
+                LLVMBuildNot(self.builder, operand, c"bitnot".as_ptr())
+            },
+        }
+    }
+}
+
+/// Converts a message returned by LLVM into a `String`, freeing the original.
+fn take_llvm_message(msg: *mut c_char) -> Option<String> {
+    if msg.is_null() {
+        return None;
+    }
+
+    let message = unsafe { CStr::from_ptr(msg) }
+        .to_string_lossy()
+        .into_owned();
+    unsafe { LLVMDisposeMessage(msg) };
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm::core::LLVMPrintModuleToString;
+    use zoid_lowering::ZoidLoweringContext;
+    use zoid_parser::Parser;
+    use zoid_span::FileId;
+
+    use super::*;
+
+    /// Generates and verifies `source`, which must compile, with overflow
+    /// checks, and returns the IR of the function `name`.
+    fn function_ir(source: &str, name: &str) -> String {
+        let (program, diagnostics) = Parser::new(FileId(0), "test.zd", source).parse();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let mut lowering = ZoidLoweringContext::new(program);
+        let hlir = lowering.lower();
+        assert!(
+            lowering.diagnostics.is_empty(),
+            "{:?}",
+            lowering.diagnostics
+        );
+
+        let mut codegen = ZoidCodeGenContext::new(hlir).with_overflow_checks(true);
+        codegen.codegen();
+        codegen.verify();
+        assert!(codegen.diagnostics.is_empty(), "{:?}", codegen.diagnostics);
+
+        let ir = take_llvm_message(unsafe { LLVMPrintModuleToString(codegen.module) }).unwrap();
+        let start = ir
+            .lines()
+            .find(|line| line.starts_with("define") && line.contains(&format!("@{}(", name)))
+            .and_then(|line| ir.find(line))
+            .unwrap_or_else(|| panic!("no `{}` in:\n{}", name, ir));
+        let end = start + ir[start..].find("\n}\n").unwrap();
+        ir[start..end].to_string()
+    }
+
+    #[test]
+    fn overflow_check_writes_with_c_library() {
+        let panic = function_ir(
+            "fn main(): i32 {
+                let a: i8 = 127;
+                return (a + 1) as i32;
+            }",
+            "__zoid_panic",
+        );
+        assert!(
+            panic.contains("call i64 @write(i32 2, ptr %0, i64 %1)"),
+            "{}",
+            panic
+        );
+    }
+
+    #[test]
+    fn overflow_check_with_program_write() {
+        let source = "fn write(x: i32): i32 {
+                return x;
+            }
+            fn main(): i32 {
+                let a: i8 = 127;
+                return (a + write(1) as i8) as i32;
+            }";
+        let panic = function_ir(source, "__zoid_panic");
+        assert!(!panic.contains("@write"), "{}", panic);
+        assert!(panic.contains("call void @llvm.trap()"), "{}", panic);
+        let main = function_ir(source, "main");
+        assert!(main.contains("call void @__zoid_panic("), "{}", main);
     }
 }
@@ -0,0 +1,80 @@
+//! Every error code the compiler can emit. Codes are never reused once
+//! assigned, so they can be searched for and referenced from documentation.
+//!
+//! - `E0001..E0009`: lexing and parsing
+//! - `E0010..E0099`: name resolution and type checking
+//! - `E0900..E0999`: code generation
+
+use crate::ErrorCode;
+
+/// A character that cannot start any token.
+pub const UNKNOWN_CHARACTER: ErrorCode = ErrorCode(1);
+/// A `/*` comment without a matching `*/`.
+pub const UNTERMINATED_COMMENT: ErrorCode = ErrorCode(2);
+/// A specific token was required but something else was found.
+pub const EXPECTED_TOKEN: ErrorCode = ErrorCode(3);
+/// A type was required but something else was found.
+pub const EXPECTED_TYPE: ErrorCode = ErrorCode(4);
+/// An expression was required but something else was found.
+pub const EXPECTED_EXPRESSION: ErrorCode = ErrorCode(5);
+/// A statement was required but something else was found.
+pub const EXPECTED_STATEMENT: ErrorCode = ErrorCode(6);
+/// Something other than an item (such as a function) was found at the top
+/// level of a file.
+pub const EXPECTED_ITEM: ErrorCode = ErrorCode(7);
+/// A numeric literal with a digit outside its radix, an unknown suffix, or a
+/// value too large for any type.
+pub const INVALID_LITERAL: ErrorCode = ErrorCode(8);
+/// A `"` string literal without a closing `"`.
+pub const UNTERMINATED_STRING: ErrorCode = ErrorCode(9);
+
+/// A name was used that is not declared in the current scope.
+pub const UNRESOLVED_NAME: ErrorCode = ErrorCode(10);
+/// `return;` was used in a function that must return a value.
+pub const MISSING_RETURN_VALUE: ErrorCode = ErrorCode(11);
+/// Two types that must be the same are not.
+pub const MISMATCHED_TYPES: ErrorCode = ErrorCode(12);
+/// An integer literal whose value does not fit in its type.
+pub const LITERAL_OUT_OF_RANGE: ErrorCode = ErrorCode(13);
+/// The type of a variable could not be inferred.
+pub const TYPE_ANNOTATIONS_NEEDED: ErrorCode = ErrorCode(14);
+/// A function was called with the wrong number of arguments.
+pub const WRONG_ARGUMENT_COUNT: ErrorCode = ErrorCode(15);
+/// A function that must return a value can reach the end of its body.
+pub const MISSING_RETURN: ErrorCode = ErrorCode(16);
+/// `break` or `continue` was used outside of a loop.
+pub const OUTSIDE_OF_LOOP: ErrorCode = ErrorCode(17);
+/// The left-hand side of an assignment is not something that can be
+/// assigned to, such as a variable.
+pub const INVALID_ASSIGNMENT: ErrorCode = ErrorCode(18);
+/// A constant whose value depends on itself.
+pub const CONSTANT_CYCLE: ErrorCode = ErrorCode(19);
+/// A constant that could not be evaluated at compile time, because it
+/// overflows, divides by zero, or uses something other than literals,
+/// operators and other constants.
+pub const CONSTANT_EVALUATION: ErrorCode = ErrorCode(20);
+/// An `as` cast between types that cannot be converted, such as `f64` to
+/// `bool`.
+pub const INVALID_CAST: ErrorCode = ErrorCode(21);
+/// `.&` was used on something that has no address, such as a literal or the
+/// result of a call.
+pub const INVALID_ADDRESS_OF: ErrorCode = ErrorCode(22);
+/// An atomic intrinsic was given something other than a memory ordering, or
+/// an ordering the operation cannot have, such as `release` for a load.
+pub const INVALID_ATOMIC_ORDERING: ErrorCode = ErrorCode(23);
+/// An `extern` declaration with a calling convention other than `"C"`.
+pub const UNSUPPORTED_ABI: ErrorCode = ErrorCode(24);
+/// A `cimport`ed header could not be preprocessed, usually because it does
+/// not exist.
+pub const CIMPORT_FAILED: ErrorCode = ErrorCode(25);
+/// A pointer to `void` was dereferenced, or given to an intrinsic that
+/// accesses the value it points to.
+pub const DEREFERENCE_VOID: ErrorCode = ErrorCode(26);
+/// Two functions, or two constants, were given the same name.
+pub const DUPLICATE_DEFINITION: ErrorCode = ErrorCode(27);
+
+/// LLVM rejected the module produced by code generation. This is always a
+/// bug in the compiler.
+pub const INVALID_MODULE: ErrorCode = ErrorCode(900);
+/// The target machine for the module's target triple could not be created.
+pub const UNKNOWN_TARGET: ErrorCode = ErrorCode(901);
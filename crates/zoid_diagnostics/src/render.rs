@@ -0,0 +1,218 @@
+use std::fmt::Write;
+
+use zoid_span::{FileId, SourceMap};
+
+use crate::{Diagnostic, Label, LabelStyle, Severity};
+
+const TAB_WIDTH: usize = 4;
+
+/// Renders diagnostics for a terminal, showing each labelled line of source
+/// with the labelled code underlined:
+///
+/// ```text
+/// error[E0012]: cannot unify types `i32` and `f64`
+///  --> basic.zd:9:12
+///   |
+/// 9 |     return (b - 57) / 2;
+///   |            ^^^^^^^^^^^^ expected `i32`, found `f64`
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Renderer<'a> {
+    source_map: &'a SourceMap,
+    color: bool,
+}
+
+/// A label resolved to a position within a line of source.
+struct LineLabel<'a> {
+    line: usize,
+    /// Display column (tabs expanded) of the start of the label.
+    start: usize,
+    /// Display width of the labelled text on this line.
+    width: usize,
+    label: &'a Label,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(source_map: &'a SourceMap) -> Self {
+        Self {
+            source_map,
+            color: false,
+        }
+    }
+
+    /// Enables ANSI colors in the rendered output.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+
+        let severity_color = match diagnostic.severity {
+            Severity::Error => "1;31",
+            Severity::Warning => "1;33",
+            Severity::Note => "1;32",
+        };
+        let header = match diagnostic.code {
+            Some(code) => format!("{}[{}]", diagnostic.severity, code),
+            None => diagnostic.severity.to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(&header, severity_color),
+            self.paint(&format!(": {}", diagnostic.message), "1"),
+        );
+
+        // Group the labels by file, keeping files in the order they are first
+        // mentioned so the primary label's file comes first.
+        let mut labels: Vec<&Label> = diagnostic.labels.iter().collect();
+        labels.sort_by_key(|label| label.style != LabelStyle::Primary);
+        let mut files: Vec<FileId> = Vec::new();
+        for label in &labels {
+            if !files.contains(&label.span.file) {
+                files.push(label.span.file);
+            }
+        }
+
+        let max_line = labels
+            .iter()
+            .map(|label| self.source_map.lookup(label.span).line)
+            .max()
+            .unwrap_or(0);
+        let gutter = max_line.to_string().len();
+
+        for (i, file) in files.iter().enumerate() {
+            let mut file_labels: Vec<&Label> = labels
+                .iter()
+                .copied()
+                .filter(|label| label.span.file == *file)
+                .collect();
+
+            let location = self.source_map.lookup(file_labels[0].span);
+            let arrow = if i == 0 { "-->" } else { ":::" };
+            let _ = writeln!(
+                out,
+                "{:gutter$}{} {}:{}:{}",
+                "",
+                self.paint(arrow, "1;34"),
+                self.source_map.name(*file),
+                location.line,
+                location.column,
+            );
+            let _ = writeln!(out, "{:gutter$} {}", "", self.paint("|", "1;34"));
+
+            file_labels.sort_by_key(|label| label.span.start);
+            let line_labels: Vec<LineLabel> = file_labels
+                .iter()
+                .map(|label| self.line_label(label))
+                .collect();
+
+            let mut current_line = None;
+            for line_label in &line_labels {
+                if current_line != Some(line_label.line) {
+                    if current_line.is_some_and(|line| line + 1 < line_label.line) {
+                        let _ = writeln!(out, "{}", self.paint("...", "1;34"));
+                    }
+                    current_line = Some(line_label.line);
+                    let _ = writeln!(
+                        out,
+                        "{} {} {}",
+                        self.paint(&format!("{:>gutter$}", line_label.line), "1;34"),
+                        self.paint("|", "1;34"),
+                        expand_tabs(self.source_map.file(*file).line(line_label.line)),
+                    );
+                }
+
+                let (marker, color) = match line_label.label.style {
+                    LabelStyle::Primary => ('^', severity_color),
+                    LabelStyle::Secondary => ('-', "1;34"),
+                };
+                let underline: String =
+                    std::iter::repeat_n(marker, line_label.width.max(1)).collect();
+                let mut annotation = underline;
+                if !line_label.label.message.is_empty() {
+                    annotation.push(' ');
+                    annotation.push_str(&line_label.label.message);
+                }
+                let _ = writeln!(
+                    out,
+                    "{:gutter$} {} {:start$}{}",
+                    "",
+                    self.paint("|", "1;34"),
+                    "",
+                    self.paint(&annotation, color),
+                    start = line_label.start,
+                );
+            }
+        }
+
+        if !diagnostic.notes.is_empty() || diagnostic.help.is_some() {
+            if !files.is_empty() {
+                let _ = writeln!(out, "{:gutter$} {}", "", self.paint("|", "1;34"));
+            }
+            for note in &diagnostic.notes {
+                let _ = writeln!(
+                    out,
+                    "{:gutter$} {} {}: {}",
+                    "",
+                    self.paint("=", "1;34"),
+                    self.paint("note", "1"),
+                    note
+                );
+            }
+            if let Some(help) = &diagnostic.help {
+                let _ = writeln!(
+                    out,
+                    "{:gutter$} {} {}: {}",
+                    "",
+                    self.paint("=", "1;34"),
+                    self.paint("help", "1"),
+                    help
+                );
+            }
+        }
+
+        out
+    }
+
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn line_label<'l>(&self, label: &'l Label) -> LineLabel<'l> {
+        let file = self.source_map.file(label.span.file);
+        let (start, end) = self.source_map.lookup_range(label.span);
+        let text = file.line(start.line);
+
+        // Spans covering several lines are only underlined on their first line.
+        let start_column = (start.utf8_column - 1).min(text.len());
+        let end_column = if end.line == start.line {
+            (end.utf8_column - 1).clamp(start_column, text.len())
+        } else {
+            text.len()
+        };
+
+        LineLabel {
+            line: start.line,
+            start: display_width(&text[..start_column]),
+            width: display_width(&text[start_column..end_column]),
+            label,
+        }
+    }
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
@@ -1,54 +1,172 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+pub use zoid_span::Span;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HLIRProgram<'source> {
-    pub globals: HashMap<&'source str, HLIRType>,
-    pub prototypes: HashMap<&'source str, (Vec<HLIRType>, HLIRType)>,
+    /// The value of every `const`, evaluated at compile time.
+    pub globals: HashMap<&'source str, (HLIRType, HLIRConstant)>,
+    /// The signature of every function, including `extern` ones, which
+    /// have no entry in `functions`.
+    pub prototypes: HashMap<&'source str, HLIRPrototype>,
     pub functions: Vec<HLIRFunction<'source>>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HLIRPrototype {
+    pub parameters: Vec<HLIRType>,
+    pub return_type: HLIRType,
+    /// Whether the function takes any number of further arguments after
+    /// `parameters`, as C's `...` does.
+    pub variadic: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HLIRFunction<'source> {
     pub name: &'source str,
     pub parameters: Vec<HLIRParameter<'source>>,
     pub return_type: HLIRType,
     pub body: Vec<HLIRStatement<'source>>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HLIRParameter<'source> {
     pub name: &'source str,
+    pub binding: HLIRBindingId,
     pub ty: HLIRType,
+    pub span: Span,
 }
 
+/// Identifies a single parameter or `let`, which are unique within a
+/// program. Shadowing a variable gives the new one a different id, so code
+/// generation does not need to know about scopes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HLIRBindingId(pub usize);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HLIRStatement<'source> {
     VariableDeclaration {
         name: &'source str,
+        binding: HLIRBindingId,
         ty: HLIRType,
         value: HLIRExpression<'source>,
+        span: Span,
+    },
+    Return(Option<HLIRExpression<'source>>, Span),
+    Expression(HLIRExpression<'source>, Span),
+    Assignment {
+        target: HLIRExpression<'source>,
+        op: Option<HLIRBinaryOperator>,
+        value: HLIRExpression<'source>,
+        span: Span,
     },
-    Return(Option<HLIRExpression<'source>>),
+    If {
+        condition: HLIRExpression<'source>,
+        then_branch: Vec<HLIRStatement<'source>>,
+        else_branch: Option<Vec<HLIRStatement<'source>>>,
+        span: Span,
+    },
+    While {
+        condition: HLIRExpression<'source>,
+        body: Vec<HLIRStatement<'source>>,
+        span: Span,
+    },
+    Break(Span),
+    Continue(Span),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HLIRExpression<'source> {
-    Literal(HLIRLiteral<'source>, HLIRType),
-    Variable(&'source str, HLIRType),
+    Literal(HLIRLiteral<'source>, HLIRType, Span),
+    Variable(&'source str, HLIRBindingId, HLIRType, Span),
+    /// A reference to a `const`, whose value is in [`HLIRProgram::globals`].
+    Constant(&'source str, HLIRType, Span),
     BinaryOperation {
         lhs: Box<HLIRExpression<'source>>,
         op: HLIRBinaryOperator,
         rhs: Box<HLIRExpression<'source>>,
         ty: HLIRType,
+        span: Span,
+    },
+    UnaryOperation {
+        op: HLIRUnaryOperator,
+        operand: Box<HLIRExpression<'source>>,
+        ty: HLIRType,
+        span: Span,
+    },
+    Call {
+        callee: &'source str,
+        arguments: Vec<HLIRExpression<'source>>,
+        ty: HLIRType,
+        span: Span,
+    },
+    /// A call to a builtin that is generated inline rather than called. The
+    /// arguments do not include the orderings of atomic intrinsics, which are
+    /// part of `intrinsic`.
+    Intrinsic {
+        intrinsic: HLIRIntrinsic,
+        arguments: Vec<HLIRExpression<'source>>,
+        ty: HLIRType,
+        span: Span,
+    },
+    Cast {
+        value: Box<HLIRExpression<'source>>,
+        ty: HLIRType,
+        span: Span,
+    },
+    AddressOf {
+        operand: Box<HLIRExpression<'source>>,
+        ty: HLIRType,
+        span: Span,
+    },
+    Dereference {
+        operand: Box<HLIRExpression<'source>>,
+        ty: HLIRType,
+        span: Span,
     },
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HLIRLiteral<'source> {
-    Integer(&'source str, HLIRType),
+    /// The literal's bits in two's complement. A negated literal is folded
+    /// into a negative one, so its bits above the type's width are set.
+    Integer(u128, HLIRType),
     Float(&'source str, HLIRType),
+    Bool(bool),
+    Null,
 }
 
+/// The value of a `const`.
+#[derive(Debug, Clone, Copy)]
+pub enum HLIRConstant {
+    /// The value's bits in two's complement, so negative values of signed
+    /// types have every bit above the type's width set.
+    Integer(u128),
+    /// The value of an `f32` or `f64`. An `f32` has already been rounded to
+    /// the nearest `f32`.
+    Float(f64),
+    Bool(bool),
+    Null,
+}
+
+impl PartialEq for HLIRConstant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (HLIRConstant::Integer(a), HLIRConstant::Integer(b)) => a == b,
+            (HLIRConstant::Float(a), HLIRConstant::Float(b)) => a.to_bits() == b.to_bits(),
+            (HLIRConstant::Bool(a), HLIRConstant::Bool(b)) => a == b,
+            (HLIRConstant::Null, HLIRConstant::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for HLIRConstant {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HLIRBinaryOperator {
     Add,
@@ -56,26 +174,295 @@ pub enum HLIRBinaryOperator {
     Mul,
     Div,
     Rem,
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+    SaturatingAdd,
+    SaturatingSub,
+    SaturatingMul,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+
+    And,
+    Or,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+impl HLIRBinaryOperator {
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            HLIRBinaryOperator::Eq
+                | HLIRBinaryOperator::Ne
+                | HLIRBinaryOperator::Lt
+                | HLIRBinaryOperator::Le
+                | HLIRBinaryOperator::Gt
+                | HLIRBinaryOperator::Ge
+        )
+    }
+
+    pub fn is_logical(&self) -> bool {
+        matches!(self, HLIRBinaryOperator::And | HLIRBinaryOperator::Or)
+    }
+
+    /// Whether the operator is `+`, `-` or `*`, whose result can overflow
+    /// and which have wrapping and saturating forms.
+    pub fn is_overflowing(&self) -> bool {
+        matches!(
+            self,
+            HLIRBinaryOperator::Add | HLIRBinaryOperator::Sub | HLIRBinaryOperator::Mul
+        )
+    }
+
+    pub fn is_saturating(&self) -> bool {
+        matches!(
+            self,
+            HLIRBinaryOperator::SaturatingAdd
+                | HLIRBinaryOperator::SaturatingSub
+                | HLIRBinaryOperator::SaturatingMul
+        )
+    }
+
+    pub fn is_shift(&self) -> bool {
+        matches!(self, HLIRBinaryOperator::Shl | HLIRBinaryOperator::Shr)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HLIRUnaryOperator {
+    Neg,
+    Not,
+    BitNot,
+}
+
+/// A builtin memory operation. The first argument of each is the pointer to
+/// access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HLIRIntrinsic {
+    /// `volatile_load(p)`, which is never removed or merged with other
+    /// accesses by the optimizer.
+    VolatileLoad,
+    /// `volatile_store(p, value)`.
+    VolatileStore,
+    /// `atomic_load(p, ordering)`.
+    AtomicLoad(HLIRAtomicOrdering),
+    /// `atomic_store(p, value, ordering)`.
+    AtomicStore(HLIRAtomicOrdering),
+    /// `atomic_cmpxchg(p, expected, new, success, failure)`, which stores
+    /// `new` if `p` holds `expected` and gives the value it held.
+    AtomicCompareExchange {
+        success: HLIRAtomicOrdering,
+        failure: HLIRAtomicOrdering,
+    },
+    /// `atomic_fetch_add(p, value, ordering)`, which gives the value `p`
+    /// held before the addition.
+    AtomicFetchAdd(HLIRAtomicOrdering),
+}
+
+impl HLIRIntrinsic {
+    /// The name the intrinsic is called by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HLIRIntrinsic::VolatileLoad => "volatile_load",
+            HLIRIntrinsic::VolatileStore => "volatile_store",
+            HLIRIntrinsic::AtomicLoad(_) => "atomic_load",
+            HLIRIntrinsic::AtomicStore(_) => "atomic_store",
+            HLIRIntrinsic::AtomicCompareExchange { .. } => "atomic_cmpxchg",
+            HLIRIntrinsic::AtomicFetchAdd(_) => "atomic_fetch_add",
+        }
+    }
+}
+
+/// The memory ordering of an atomic intrinsic, named as in C11.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HLIRAtomicOrdering {
+    Relaxed,
+    Acquire,
+    Release,
+    AcqRel,
+    SeqCst,
+}
+
+impl HLIRAtomicOrdering {
+    pub fn from_name(name: &str) -> Option<HLIRAtomicOrdering> {
+        match name {
+            "relaxed" => Some(HLIRAtomicOrdering::Relaxed),
+            "acquire" => Some(HLIRAtomicOrdering::Acquire),
+            "release" => Some(HLIRAtomicOrdering::Release),
+            "acq_rel" => Some(HLIRAtomicOrdering::AcqRel),
+            "seq_cst" => Some(HLIRAtomicOrdering::SeqCst),
+            _ => None,
+        }
+    }
+}
+
+impl Display for HLIRAtomicOrdering {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            HLIRAtomicOrdering::Relaxed => write!(f, "relaxed"),
+            HLIRAtomicOrdering::Acquire => write!(f, "acquire"),
+            HLIRAtomicOrdering::Release => write!(f, "release"),
+            HLIRAtomicOrdering::AcqRel => write!(f, "acq_rel"),
+            HLIRAtomicOrdering::SeqCst => write!(f, "seq_cst"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[rustfmt::skip]
 pub enum HLIRType {
     I8, I16, I32, I64, I128,
     U8, U16, U32, U64, U128,
     F32, F64,
+    Bool,
     Void,
+    /// A pointer to a value of the boxed type.
+    Pointer(Box<HLIRType>),
 
     /// A type variable, used for type inference. If it appears after inference, it is an error.
     Var(usize),
 }
 
+impl HLIRType {
+    /// A pointer to `pointee`.
+    pub fn pointer_to(pointee: HLIRType) -> HLIRType {
+        HLIRType::Pointer(Box::new(pointee))
+    }
+
+    /// The type a pointer type points to, or `None` for other types.
+    pub fn pointee(&self) -> Option<HLIRType> {
+        match self {
+            HLIRType::Pointer(pointee) => Some((**pointee).clone()),
+            _ => None,
+        }
+    }
+
+    /// The number of bits in an integer type, or `None` for other types.
+    pub fn integer_bits(&self) -> Option<u32> {
+        match self {
+            HLIRType::I8 | HLIRType::U8 => Some(8),
+            HLIRType::I16 | HLIRType::U16 => Some(16),
+            HLIRType::I32 | HLIRType::U32 => Some(32),
+            HLIRType::I64 | HLIRType::U64 => Some(64),
+            HLIRType::I128 | HLIRType::U128 => Some(128),
+            _ => None,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, HLIRType::F32 | HLIRType::F64)
+    }
+
+    pub fn is_signed_integer(&self) -> bool {
+        matches!(
+            self,
+            HLIRType::I8 | HLIRType::I16 | HLIRType::I32 | HLIRType::I64 | HLIRType::I128
+        )
+    }
+
+    /// The largest value of an integer type, or `None` for other types.
+    pub fn integer_max(&self) -> Option<u128> {
+        let bits = self.integer_bits()?;
+        let value_bits = if self.is_signed_integer() {
+            bits - 1
+        } else {
+            bits
+        };
+        Some(u128::MAX >> (128 - value_bits))
+    }
+
+    /// Truncates `value` to the width of an integer type, keeping the bits
+    /// above it as copies of the sign bit for signed types, as constants are
+    /// stored (see [`HLIRConstant::Integer`]).
+    pub fn wrap_integer(&self, value: u128) -> u128 {
+        let Some(bits) = self.integer_bits() else {
+            return value;
+        };
+        let shift = 128 - bits;
+        if self.is_signed_integer() {
+            (((value << shift) as i128) >> shift) as u128
+        } else {
+            (value << shift) >> shift
+        }
+    }
+}
+
+impl HLIRStatement<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            HLIRStatement::VariableDeclaration { span, .. } => *span,
+            HLIRStatement::Return(_, span) => *span,
+            HLIRStatement::Expression(_, span) => *span,
+            HLIRStatement::Assignment { span, .. } => *span,
+            HLIRStatement::If { span, .. } => *span,
+            HLIRStatement::While { span, .. } => *span,
+            HLIRStatement::Break(span) => *span,
+            HLIRStatement::Continue(span) => *span,
+        }
+    }
+}
+
 impl HLIRExpression<'_> {
     pub fn ty(&self) -> HLIRType {
         match self {
-            HLIRExpression::Literal(_, ty) => *ty,
-            HLIRExpression::Variable(_, ty) => *ty,
-            HLIRExpression::BinaryOperation { ty, .. } => *ty,
+            HLIRExpression::Literal(_, ty, _) => ty.clone(),
+            HLIRExpression::Variable(_, _, ty, _) => ty.clone(),
+            HLIRExpression::Constant(_, ty, _) => ty.clone(),
+            HLIRExpression::BinaryOperation { ty, .. } => ty.clone(),
+            HLIRExpression::UnaryOperation { ty, .. } => ty.clone(),
+            HLIRExpression::Call { ty, .. } => ty.clone(),
+            HLIRExpression::Intrinsic { ty, .. } => ty.clone(),
+            HLIRExpression::Cast { ty, .. } => ty.clone(),
+            HLIRExpression::AddressOf { ty, .. } => ty.clone(),
+            HLIRExpression::Dereference { ty, .. } => ty.clone(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            HLIRExpression::Literal(_, _, span) => *span,
+            HLIRExpression::Variable(_, _, _, span) => *span,
+            HLIRExpression::Constant(_, _, span) => *span,
+            HLIRExpression::BinaryOperation { span, .. } => *span,
+            HLIRExpression::UnaryOperation { span, .. } => *span,
+            HLIRExpression::Call { span, .. } => *span,
+            HLIRExpression::Intrinsic { span, .. } => *span,
+            HLIRExpression::Cast { span, .. } => *span,
+            HLIRExpression::AddressOf { span, .. } => *span,
+            HLIRExpression::Dereference { span, .. } => *span,
+        }
+    }
+}
+
+impl Display for HLIRType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            HLIRType::I8 => write!(f, "i8"),
+            HLIRType::I16 => write!(f, "i16"),
+            HLIRType::I32 => write!(f, "i32"),
+            HLIRType::I64 => write!(f, "i64"),
+            HLIRType::I128 => write!(f, "i128"),
+            HLIRType::U8 => write!(f, "u8"),
+            HLIRType::U16 => write!(f, "u16"),
+            HLIRType::U32 => write!(f, "u32"),
+            HLIRType::U64 => write!(f, "u64"),
+            HLIRType::U128 => write!(f, "u128"),
+            HLIRType::F32 => write!(f, "f32"),
+            HLIRType::F64 => write!(f, "f64"),
+            HLIRType::Bool => write!(f, "bool"),
+            HLIRType::Void => write!(f, "void"),
+            HLIRType::Pointer(pointee) => write!(f, "*{}", pointee),
+            HLIRType::Var(_) => write!(f, "_"),
         }
     }
 }
@@ -1,6 +1,8 @@
 use std::{iter::Peekable, str::Chars};
 
 use token::{Token, TokenKind};
+use zoid_diagnostics::{codes, Diagnostic};
+use zoid_span::{FileId, Span};
 
 pub mod token;
 
@@ -14,23 +16,28 @@ impl<'fname, 'source> Iterator for Lexer<'fname, 'source> {
 
 #[derive(Debug, Clone)]
 pub struct Lexer<'fname, 'source> {
+    file_id: FileId,
     file: &'fname str,
     source: &'source str,
     chars: Peekable<Chars<'source>>,
+    /// Byte offset of the next character in `source`.
     pos: usize,
     line: usize,
     column: usize,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'fname, 'source> Lexer<'fname, 'source> {
-    pub fn new(file: &'fname str, source: &'source str) -> Self {
+    pub fn new(file_id: FileId, file: &'fname str, source: &'source str) -> Self {
         Self {
+            file_id,
             file,
             source,
             chars: source.chars().peekable(),
             pos: 0,
             line: 1,
             column: 1,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -39,6 +46,14 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         self.pos = 0;
         self.line = 1;
         self.column = 1;
+        self.diagnostics.clear();
+    }
+
+    /// Takes the errors found in the tokens produced so far, such as unknown
+    /// characters. The offending tokens are still produced as
+    /// [`TokenKind::Unknown`].
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
     }
 
     pub fn next_token(&mut self) -> Option<Token<'fname, 'source>> {
@@ -48,9 +63,41 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         let col = self.column;
 
         match self.next_char()? {
-            '+' => self.tok(start, line, col, TokenKind::OpAdd),
-            '-' => self.tok(start, line, col, TokenKind::OpSub),
-            '*' => self.tok(start, line, col, TokenKind::OpMul),
+            '+' => self.tok_arithmetic(
+                start,
+                line,
+                col,
+                [
+                    (TokenKind::OpAdd, TokenKind::OpAddAssign),
+                    (TokenKind::OpWrappingAdd, TokenKind::OpWrappingAddAssign),
+                    (TokenKind::OpSaturatingAdd, TokenKind::OpSaturatingAddAssign),
+                ],
+            ),
+            '-' => self.tok_arithmetic(
+                start,
+                line,
+                col,
+                [
+                    (TokenKind::OpSub, TokenKind::OpSubAssign),
+                    (TokenKind::OpWrappingSub, TokenKind::OpWrappingSubAssign),
+                    (TokenKind::OpSaturatingSub, TokenKind::OpSaturatingSubAssign),
+                ],
+            ),
+            // The `*` of a dereference, `p.*`, is never part of a longer
+            // operator, so `p.*|mask` is `p.* | mask`.
+            '*' if self.source[..start].ends_with('.') => {
+                self.tok(start, line, col, TokenKind::OpMul)
+            }
+            '*' => self.tok_arithmetic(
+                start,
+                line,
+                col,
+                [
+                    (TokenKind::OpMul, TokenKind::OpMulAssign),
+                    (TokenKind::OpWrappingMul, TokenKind::OpWrappingMulAssign),
+                    (TokenKind::OpSaturatingMul, TokenKind::OpSaturatingMulAssign),
+                ],
+            ),
             '/' => match self.peek_char() {
                 Some('/') => {
                     self.next_char();
@@ -59,27 +106,117 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
                 }
                 Some('*') => {
                     self.next_char();
-                    self.consume_block_comment();
+                    self.consume_block_comment(start);
                     self.next_token()
                 }
-                _ => self.tok(start, line, col, TokenKind::OpDiv),
+                _ => self.tok_assign(start, line, col, TokenKind::OpDiv, TokenKind::OpDivAssign),
             },
-            '%' => self.tok(start, line, col, TokenKind::OpRem),
+            '%' => self.tok_assign(start, line, col, TokenKind::OpRem, TokenKind::OpRemAssign),
 
-            '=' => self.tok(start, line, col, TokenKind::OpAssign),
+            '=' => match self.peek_char() {
+                Some('=') => {
+                    self.next_char();
+                    self.tok(start, line, col, TokenKind::OpEq)
+                }
+                _ => self.tok(start, line, col, TokenKind::OpAssign),
+            },
+            '!' => match self.peek_char() {
+                Some('=') => {
+                    self.next_char();
+                    self.tok(start, line, col, TokenKind::OpNe)
+                }
+                _ => self.tok(start, line, col, TokenKind::OpNot),
+            },
+            '<' => match self.peek_char() {
+                Some('=') => {
+                    self.next_char();
+                    self.tok(start, line, col, TokenKind::OpLe)
+                }
+                Some('<') => {
+                    self.next_char();
+                    self.tok_assign(start, line, col, TokenKind::OpShl, TokenKind::OpShlAssign)
+                }
+                _ => self.tok(start, line, col, TokenKind::OpLt),
+            },
+            '>' => match self.peek_char() {
+                Some('=') => {
+                    self.next_char();
+                    self.tok(start, line, col, TokenKind::OpGe)
+                }
+                Some('>') => {
+                    self.next_char();
+                    self.tok_assign(start, line, col, TokenKind::OpShr, TokenKind::OpShrAssign)
+                }
+                _ => self.tok(start, line, col, TokenKind::OpGt),
+            },
+            // Likewise the `&` of an address-of, `x.&`, so `x.&&&y` is
+            // `x.& && y`.
+            '&' if self.source[..start].ends_with('.') => {
+                self.tok(start, line, col, TokenKind::OpBitAnd)
+            }
+            '&' => match self.peek_char() {
+                Some('&') => {
+                    self.next_char();
+                    self.tok(start, line, col, TokenKind::OpAnd)
+                }
+                _ => self.tok_assign(
+                    start,
+                    line,
+                    col,
+                    TokenKind::OpBitAnd,
+                    TokenKind::OpBitAndAssign,
+                ),
+            },
+            '|' => match self.peek_char() {
+                Some('|') => {
+                    self.next_char();
+                    self.tok(start, line, col, TokenKind::OpOr)
+                }
+                _ => self.tok_assign(
+                    start,
+                    line,
+                    col,
+                    TokenKind::OpBitOr,
+                    TokenKind::OpBitOrAssign,
+                ),
+            },
+            '^' => self.tok_assign(
+                start,
+                line,
+                col,
+                TokenKind::OpBitXor,
+                TokenKind::OpBitXorAssign,
+            ),
+            '~' => self.tok(start, line, col, TokenKind::OpBitNot),
 
             ';' => self.tok(start, line, col, TokenKind::Semicolon),
             ':' => self.tok(start, line, col, TokenKind::Colon),
             ',' => self.tok(start, line, col, TokenKind::Comma),
+            '.' if self.peek_char() == Some('.') && self.peek_nth_char(1) == Some('.') => {
+                self.next_char();
+                self.next_char();
+                self.tok(start, line, col, TokenKind::Ellipsis)
+            }
+            '.' => self.tok(start, line, col, TokenKind::Dot),
             '(' => self.tok(start, line, col, TokenKind::LParen),
             ')' => self.tok(start, line, col, TokenKind::RParen),
             '{' => self.tok(start, line, col, TokenKind::LBrace),
             '}' => self.tok(start, line, col, TokenKind::RBrace),
+            '[' => self.tok(start, line, col, TokenKind::LBracket),
+            ']' => self.tok(start, line, col, TokenKind::RBracket),
 
+            '"' => self.tokenize_string_literal(start, line, col),
             '0'..='9' => self.tokenize_numeric_literal(start, line, col),
             'a'..='z' | 'A'..='Z' | '_' => self.tokenize_identifier(start, line, col),
 
-            _ => self.tok(start, line, col, TokenKind::Unknown),
+            c => {
+                self.diagnostics.push(
+                    Diagnostic::error(format!("unknown character `{}`", c.escape_debug()))
+                        .with_code(codes::UNKNOWN_CHARACTER)
+                        .with_primary(Span::new(self.file_id, start, self.pos), ""),
+                );
+                self.tok(start, line, col, TokenKind::Unknown)
+            }
         }
     }
 
@@ -89,9 +226,73 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         line: usize,
         col: usize,
     ) -> Option<Token<'fname, 'source>> {
+        // Prefixed literals such as `0xFF` are always integers. The digits and
+        // any suffix are checked by the parser, which knows the radix.
+        if self.source[start..].starts_with('0')
+            && matches!(self.peek_char(), Some('x' | 'o' | 'b'))
+        {
+            self.next_char();
+            self.consume_identifier();
+            let value = &self.source[start..self.pos];
+            return self.tok_v(start, line, col, TokenKind::IntegerLiteral, value);
+        }
+
         self.consume_numeric_literal();
+        let mut kind = TokenKind::IntegerLiteral;
+
+        // A `.` is only part of the literal when a digit follows it.
+        if self.peek_char() == Some('.')
+            && self.peek_nth_char(1).is_some_and(|c| c.is_ascii_digit())
+        {
+            self.next_char();
+            self.consume_numeric_literal();
+            kind = TokenKind::FloatLiteral;
+        }
+
+        if self.consume_exponent() {
+            kind = TokenKind::FloatLiteral;
+        }
+
+        // A type suffix such as `u8`. Anything else directly after the digits
+        // is kept in the token so the parser can report it as a bad suffix.
+        if kind == TokenKind::IntegerLiteral {
+            self.consume_identifier();
+        }
+
         let value = &self.source[start..self.pos];
-        self.tok_v(start, line, col, TokenKind::IntegerLiteral, value)
+        self.tok_v(start, line, col, kind, value)
+    }
+
+    fn tokenize_string_literal(
+        &mut self,
+        start: usize,
+        line: usize,
+        col: usize,
+    ) -> Option<Token<'fname, 'source>> {
+        loop {
+            match self.next_char() {
+                Some('"') => break,
+                // The escaped character cannot end the string.
+                Some('\\') => {
+                    self.next_char();
+                }
+                Some(_) => {}
+                None => {
+                    self.diagnostics.push(
+                        Diagnostic::error("unterminated string literal")
+                            .with_code(codes::UNTERMINATED_STRING)
+                            .with_primary(
+                                Span::new(self.file_id, start, start + 1),
+                                "string starts here",
+                            ),
+                    );
+                    return self.tok(start, line, col, TokenKind::Unknown);
+                }
+            }
+        }
+
+        let value = &self.source[start + 1..self.pos - 1];
+        self.tok_v(start, line, col, TokenKind::StringLiteral, value)
     }
 
     fn tokenize_identifier(
@@ -103,24 +304,7 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         self.consume_identifier();
         let value = &self.source[start..self.pos];
 
-        let kind = match value {
-            "i8" => TokenKind::TypeI8,
-            "i16" => TokenKind::TypeI16,
-            "i32" => TokenKind::TypeI32,
-            "i64" => TokenKind::TypeI64,
-            "i128" => TokenKind::TypeI128,
-            "u8" => TokenKind::TypeU8,
-            "u16" => TokenKind::TypeU16,
-            "u32" => TokenKind::TypeU32,
-            "u64" => TokenKind::TypeU64,
-            "u128" => TokenKind::TypeU128,
-            "f32" => TokenKind::TypeF32,
-            "f64" => TokenKind::TypeF64,
-            "fn" => TokenKind::KeywordFn,
-            "let" => TokenKind::KeywordLet,
-            "return" => TokenKind::KeywordReturn,
-            _ => TokenKind::Identifier,
-        };
+        let kind = TokenKind::keyword(value).unwrap_or(TokenKind::Identifier);
         if kind == TokenKind::Identifier {
             self.tok_v(start, line, col, kind, value)
         } else {
@@ -136,6 +320,7 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         kind: TokenKind,
     ) -> Option<Token<'fname, 'source>> {
         Some(Token {
+            file_id: self.file_id,
             file: self.file,
             start,
             end: self.pos,
@@ -146,6 +331,49 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         })
     }
 
+    /// Produces `assign_kind` if the operator is followed by a `=`, as in
+    /// `+=`, and `kind` otherwise.
+    fn tok_assign(
+        &mut self,
+        start: usize,
+        line: usize,
+        col: usize,
+        kind: TokenKind,
+        assign_kind: TokenKind,
+    ) -> Option<Token<'fname, 'source>> {
+        if self.peek_char() == Some('=') {
+            self.next_char();
+            self.tok(start, line, col, assign_kind)
+        } else {
+            self.tok(start, line, col, kind)
+        }
+    }
+
+    /// Produces the wrapping form of an arithmetic operator if it is followed
+    /// by `%`, as in `+%`, the saturating form if it is followed by `|`, as in
+    /// `+|`, and the plain operator otherwise. `kinds` holds the plain,
+    /// wrapping and saturating forms with their assignment forms.
+    fn tok_arithmetic(
+        &mut self,
+        start: usize,
+        line: usize,
+        col: usize,
+        kinds: [(TokenKind, TokenKind); 3],
+    ) -> Option<Token<'fname, 'source>> {
+        let (kind, assign_kind) = match self.peek_char() {
+            Some('%') => {
+                self.next_char();
+                kinds[1]
+            }
+            Some('|') => {
+                self.next_char();
+                kinds[2]
+            }
+            _ => kinds[0],
+        };
+        self.tok_assign(start, line, col, kind, assign_kind)
+    }
+
     fn tok_v(
         &self,
         start: usize,
@@ -155,6 +383,7 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         value: &'source str,
     ) -> Option<Token<'fname, 'source>> {
         Some(Token {
+            file_id: self.file_id,
             file: self.file,
             start,
             end: self.pos,
@@ -167,7 +396,7 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
 
     fn next_char(&mut self) -> Option<char> {
         let c = self.chars.next()?;
-        self.pos += 1;
+        self.pos += c.len_utf8();
         self.column += 1;
         if c == '\n' {
             self.line += 1;
@@ -180,6 +409,11 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         self.chars.peek().copied()
     }
 
+    /// Looks `n` characters past the next one without consuming anything.
+    fn peek_nth_char(&self, n: usize) -> Option<char> {
+        self.chars.clone().nth(n)
+    }
+
     fn consume_while<F>(&mut self, mut f: F) -> &'source str
     where
         F: FnMut(char) -> bool,
@@ -202,22 +436,28 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         self.consume_while(|c| c != '\n');
     }
 
-    fn consume_block_comment(&mut self) {
+    fn consume_block_comment(&mut self, start: usize) {
         let mut depth = 1;
         while depth > 0 {
-            let c = self.next_char().unwrap();
+            let Some(c) = self.next_char() else {
+                self.diagnostics.push(
+                    Diagnostic::error("unterminated block comment")
+                        .with_code(codes::UNTERMINATED_COMMENT)
+                        .with_primary(
+                            Span::new(self.file_id, start, start + 2),
+                            "comment starts here",
+                        ),
+                );
+                return;
+            };
             match c {
-                '/' => {
-                    if self.peek_char() == Some('*') {
-                        self.next_char();
-                        depth += 1;
-                    }
+                '/' if self.peek_char() == Some('*') => {
+                    self.next_char();
+                    depth += 1;
                 }
-                '*' => {
-                    if self.peek_char() == Some('/') {
-                        self.next_char();
-                        depth -= 1;
-                    }
+                '*' if self.peek_char() == Some('/') => {
+                    self.next_char();
+                    depth -= 1;
                 }
                 _ => {}
             }
@@ -228,7 +468,138 @@ impl<'fname, 'source> Lexer<'fname, 'source> {
         self.consume_while(|c| c.is_ascii_alphanumeric() || c == '_')
     }
 
+    /// Consumes decimal digits along with any `_` separators between them.
     fn consume_numeric_literal(&mut self) -> &'source str {
-        self.consume_while(|c| c.is_ascii_digit())
+        self.consume_while(|c| c.is_ascii_digit() || c == '_')
+    }
+
+    /// Consumes an exponent such as `e10` or `E-4`, if one follows. Returns
+    /// whether anything was consumed.
+    fn consume_exponent(&mut self) -> bool {
+        if !matches!(self.peek_char(), Some('e' | 'E')) {
+            return false;
+        }
+
+        let digits_at = match self.peek_nth_char(1) {
+            Some('+' | '-') => 2,
+            _ => 1,
+        };
+        if !self
+            .peek_nth_char(digits_at)
+            .is_some_and(|c| c.is_ascii_digit())
+        {
+            return false;
+        }
+
+        for _ in 0..digits_at {
+            self.next_char();
+        }
+        self.consume_numeric_literal();
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        Lexer::new(FileId(0), "test.zd", source)
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn byte_offsets_after_non_ascii() {
+        let source = "// コメント\nlet s = \"größe\"; x";
+        let tokens: Vec<_> = Lexer::new(FileId(0), "test.zd", source).collect();
+        assert_eq!(tokens[3].value, Some("größe"));
+        let x = tokens.last().unwrap();
+        assert_eq!(&source[x.start..x.end], "x");
+        assert_eq!((x.line, x.column), (2, 18));
+    }
+
+    #[test]
+    fn saturating_and_wrapping_operators() {
+        assert_eq!(
+            kinds("a *| b +% c -|= d"),
+            [
+                TokenKind::Identifier,
+                TokenKind::OpSaturatingMul,
+                TokenKind::Identifier,
+                TokenKind::OpWrappingAdd,
+                TokenKind::Identifier,
+                TokenKind::OpSaturatingSubAssign,
+                TokenKind::Identifier,
+            ]
+        );
+    }
+
+    #[test]
+    fn dereference_before_operator() {
+        assert_eq!(
+            kinds("p.*|mask"),
+            [
+                TokenKind::Identifier,
+                TokenKind::Dot,
+                TokenKind::OpMul,
+                TokenKind::OpBitOr,
+                TokenKind::Identifier,
+            ]
+        );
+        assert_eq!(
+            kinds("p.*%2"),
+            [
+                TokenKind::Identifier,
+                TokenKind::Dot,
+                TokenKind::OpMul,
+                TokenKind::OpRem,
+                TokenKind::IntegerLiteral,
+            ]
+        );
+        assert_eq!(
+            kinds("p.*=1"),
+            [
+                TokenKind::Identifier,
+                TokenKind::Dot,
+                TokenKind::OpMul,
+                TokenKind::OpAssign,
+                TokenKind::IntegerLiteral,
+            ]
+        );
+    }
+
+    #[test]
+    fn address_of_before_operator() {
+        assert_eq!(
+            kinds("p.&==q"),
+            [
+                TokenKind::Identifier,
+                TokenKind::Dot,
+                TokenKind::OpBitAnd,
+                TokenKind::OpEq,
+                TokenKind::Identifier,
+            ]
+        );
+        assert_eq!(
+            kinds("x.&&&y"),
+            [
+                TokenKind::Identifier,
+                TokenKind::Dot,
+                TokenKind::OpBitAnd,
+                TokenKind::OpAnd,
+                TokenKind::Identifier,
+            ]
+        );
+        assert_eq!(
+            kinds("x.&&"),
+            [
+                TokenKind::Identifier,
+                TokenKind::Dot,
+                TokenKind::OpBitAnd,
+                TokenKind::OpBitAnd,
+            ]
+        );
     }
 }
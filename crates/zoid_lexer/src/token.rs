@@ -1,11 +1,19 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
+use zoid_span::{FileId, Span};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Token<'fname, 'source> {
+    pub file_id: FileId,
     pub file: &'fname str,
+    /// Byte offset of the first byte of the token.
     pub start: usize,
+    /// Byte offset just past the last byte of the token.
     pub end: usize,
+    /// 1-based line of the start of the token.
     pub line: usize,
+    /// 1-based column of the start of the token, counted in `char`s. Use a
+    /// `SourceMap` for UTF-8 or UTF-16 columns.
     pub column: usize,
     pub kind: TokenKind,
     pub value: Option<&'source str>,
@@ -20,19 +28,68 @@ pub enum TokenKind {
     OpMul,
     OpDiv,
     OpRem,
+    OpWrappingAdd,
+    OpWrappingSub,
+    OpWrappingMul,
+    OpSaturatingAdd,
+    OpSaturatingSub,
+    OpSaturatingMul,
+
+    OpEq,
+    OpNe,
+    OpLt,
+    OpLe,
+    OpGt,
+    OpGe,
+
+    OpAnd,
+    OpOr,
+    OpNot,
+
+    OpBitNot,
+    OpBitAnd,
+    OpBitOr,
+    OpBitXor,
+    OpShl,
+    OpShr,
 
     OpAssign,
+    OpAddAssign,
+    OpSubAssign,
+    OpMulAssign,
+    OpDivAssign,
+    OpRemAssign,
+    OpWrappingAddAssign,
+    OpWrappingSubAssign,
+    OpWrappingMulAssign,
+    OpSaturatingAddAssign,
+    OpSaturatingSubAssign,
+    OpSaturatingMulAssign,
+    OpBitAndAssign,
+    OpBitOrAssign,
+    OpBitXorAssign,
+    OpShlAssign,
+    OpShrAssign,
 
     Semicolon,
     Colon,
     Comma,
+    Dot,
+    /// `...`, marking a variadic `extern` function.
+    Ellipsis,
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
 
     Identifier,
     IntegerLiteral,
+    FloatLiteral,
+    /// A `"` delimited string. The value is the text between the quotes,
+    /// with any escapes left as written.
+    StringLiteral,
 
     TypeI8,
     TypeI16,
@@ -46,12 +103,76 @@ pub enum TokenKind {
     TypeU128,
     TypeF32,
     TypeF64,
-    
+    TypeBool,
+
     TypeVoid,
 
     KeywordFn,
+    KeywordExtern,
+    KeywordCImport,
+    KeywordConst,
     KeywordLet,
     KeywordReturn,
+    KeywordIf,
+    KeywordElse,
+    KeywordWhile,
+    KeywordBreak,
+    KeywordContinue,
+    KeywordAnd,
+    KeywordOr,
+    KeywordNot,
+    KeywordAs,
+    KeywordNull,
+    KeywordTrue,
+    KeywordFalse,
+}
+
+impl TokenKind {
+    /// The keyword or type name spelled `name`, if it is one rather than an
+    /// identifier.
+    pub fn keyword(name: &str) -> Option<TokenKind> {
+        let kind = match name {
+            "i8" => TokenKind::TypeI8,
+            "i16" => TokenKind::TypeI16,
+            "i32" => TokenKind::TypeI32,
+            "i64" => TokenKind::TypeI64,
+            "i128" => TokenKind::TypeI128,
+            "u8" => TokenKind::TypeU8,
+            "u16" => TokenKind::TypeU16,
+            "u32" => TokenKind::TypeU32,
+            "u64" => TokenKind::TypeU64,
+            "u128" => TokenKind::TypeU128,
+            "f32" => TokenKind::TypeF32,
+            "f64" => TokenKind::TypeF64,
+            "bool" => TokenKind::TypeBool,
+            "fn" => TokenKind::KeywordFn,
+            "extern" => TokenKind::KeywordExtern,
+            "cimport" => TokenKind::KeywordCImport,
+            "const" => TokenKind::KeywordConst,
+            "let" => TokenKind::KeywordLet,
+            "return" => TokenKind::KeywordReturn,
+            "if" => TokenKind::KeywordIf,
+            "else" => TokenKind::KeywordElse,
+            "while" => TokenKind::KeywordWhile,
+            "break" => TokenKind::KeywordBreak,
+            "continue" => TokenKind::KeywordContinue,
+            "and" => TokenKind::KeywordAnd,
+            "or" => TokenKind::KeywordOr,
+            "not" => TokenKind::KeywordNot,
+            "as" => TokenKind::KeywordAs,
+            "null" => TokenKind::KeywordNull,
+            "true" => TokenKind::KeywordTrue,
+            "false" => TokenKind::KeywordFalse,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+impl Token<'_, '_> {
+    pub fn span(&self) -> Span {
+        Span::new(self.file_id, self.start, self.end)
+    }
 }
 
 impl Display for Token<'_, '_> {
@@ -69,3 +190,103 @@ impl Display for Token<'_, '_> {
         write!(f, " ]")
     }
 }
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let s = match self {
+            TokenKind::Unknown => "unknown token",
+            TokenKind::OpAdd => "`+`",
+            TokenKind::OpSub => "`-`",
+            TokenKind::OpMul => "`*`",
+            TokenKind::OpDiv => "`/`",
+            TokenKind::OpRem => "`%`",
+            TokenKind::OpWrappingAdd => "`+%`",
+            TokenKind::OpWrappingSub => "`-%`",
+            TokenKind::OpWrappingMul => "`*%`",
+            TokenKind::OpSaturatingAdd => "`+|`",
+            TokenKind::OpSaturatingSub => "`-|`",
+            TokenKind::OpSaturatingMul => "`*|`",
+            TokenKind::OpEq => "`==`",
+            TokenKind::OpNe => "`!=`",
+            TokenKind::OpLt => "`<`",
+            TokenKind::OpLe => "`<=`",
+            TokenKind::OpGt => "`>`",
+            TokenKind::OpGe => "`>=`",
+            TokenKind::OpAnd => "`&&`",
+            TokenKind::OpOr => "`||`",
+            TokenKind::OpNot => "`!`",
+            TokenKind::OpBitNot => "`~`",
+            TokenKind::OpBitAnd => "`&`",
+            TokenKind::OpBitOr => "`|`",
+            TokenKind::OpBitXor => "`^`",
+            TokenKind::OpShl => "`<<`",
+            TokenKind::OpShr => "`>>`",
+            TokenKind::OpAssign => "`=`",
+            TokenKind::OpAddAssign => "`+=`",
+            TokenKind::OpSubAssign => "`-=`",
+            TokenKind::OpMulAssign => "`*=`",
+            TokenKind::OpDivAssign => "`/=`",
+            TokenKind::OpRemAssign => "`%=`",
+            TokenKind::OpWrappingAddAssign => "`+%=`",
+            TokenKind::OpWrappingSubAssign => "`-%=`",
+            TokenKind::OpWrappingMulAssign => "`*%=`",
+            TokenKind::OpSaturatingAddAssign => "`+|=`",
+            TokenKind::OpSaturatingSubAssign => "`-|=`",
+            TokenKind::OpSaturatingMulAssign => "`*|=`",
+            TokenKind::OpBitAndAssign => "`&=`",
+            TokenKind::OpBitOrAssign => "`|=`",
+            TokenKind::OpBitXorAssign => "`^=`",
+            TokenKind::OpShlAssign => "`<<=`",
+            TokenKind::OpShrAssign => "`>>=`",
+            TokenKind::Semicolon => "`;`",
+            TokenKind::Colon => "`:`",
+            TokenKind::Comma => "`,`",
+            TokenKind::Dot => "`.`",
+            TokenKind::Ellipsis => "`...`",
+            TokenKind::LParen => "`(`",
+            TokenKind::RParen => "`)`",
+            TokenKind::LBrace => "`{`",
+            TokenKind::RBrace => "`}`",
+            TokenKind::LBracket => "`[`",
+            TokenKind::RBracket => "`]`",
+            TokenKind::Identifier => "identifier",
+            TokenKind::IntegerLiteral => "integer literal",
+            TokenKind::FloatLiteral => "float literal",
+            TokenKind::StringLiteral => "string literal",
+            TokenKind::TypeI8 => "`i8`",
+            TokenKind::TypeI16 => "`i16`",
+            TokenKind::TypeI32 => "`i32`",
+            TokenKind::TypeI64 => "`i64`",
+            TokenKind::TypeI128 => "`i128`",
+            TokenKind::TypeU8 => "`u8`",
+            TokenKind::TypeU16 => "`u16`",
+            TokenKind::TypeU32 => "`u32`",
+            TokenKind::TypeU64 => "`u64`",
+            TokenKind::TypeU128 => "`u128`",
+            TokenKind::TypeF32 => "`f32`",
+            TokenKind::TypeF64 => "`f64`",
+            TokenKind::TypeBool => "`bool`",
+            TokenKind::TypeVoid => "`void`",
+            TokenKind::KeywordFn => "`fn`",
+            TokenKind::KeywordExtern => "`extern`",
+            TokenKind::KeywordCImport => "`cimport`",
+            TokenKind::KeywordConst => "`const`",
+            TokenKind::KeywordLet => "`let`",
+            TokenKind::KeywordReturn => "`return`",
+            TokenKind::KeywordIf => "`if`",
+            TokenKind::KeywordElse => "`else`",
+            TokenKind::KeywordWhile => "`while`",
+            TokenKind::KeywordBreak => "`break`",
+            TokenKind::KeywordContinue => "`continue`",
+            TokenKind::KeywordAnd => "`and`",
+            TokenKind::KeywordOr => "`or`",
+            TokenKind::KeywordNot => "`not`",
+            TokenKind::KeywordAs => "`as`",
+            TokenKind::KeywordNull => "`null`",
+            TokenKind::KeywordTrue => "`true`",
+            TokenKind::KeywordFalse => "`false`",
+        };
+
+        write!(f, "{}", s)
+    }
+}
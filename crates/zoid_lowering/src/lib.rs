@@ -2,15 +2,58 @@ use std::collections::HashMap;
 
 use zoid_ast::{
     BinaryOperator, Expression, Literal, Parameter, Program, Statement, TopLevelExpression, Type,
+    UnaryOperator,
 };
+use zoid_diagnostics::{codes, Diagnostic};
 use zoid_hlir::{
-    HLIRBinaryOperator, HLIRExpression, HLIRFunction, HLIRLiteral, HLIRParameter, HLIRProgram,
-    HLIRStatement, HLIRType,
+    HLIRAtomicOrdering, HLIRBinaryOperator, HLIRBindingId, HLIRConstant, HLIRExpression,
+    HLIRFunction, HLIRIntrinsic, HLIRLiteral, HLIRParameter, HLIRProgram, HLIRPrototype,
+    HLIRStatement, HLIRType, HLIRUnaryOperator, Span,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Constraint<'source> {
-    Equal(HLIRType, HLIRType),
+    /// The two types must be the same. The first type is the one that is
+    /// expected and the span is the code that must have that type.
+    Equal(HLIRType, HLIRType, Span),
+    /// The type must be a floating point type, as it is the type of the float
+    /// literal at the span. Checked once every `Equal` constraint is solved,
+    /// defaulting to `f64` if nothing else decided the type.
+    Float(HLIRType, Span),
+    /// The type must be an integer type, as it is the type of the integer
+    /// literal at the span. Defaults to `i32` like `Float` defaults to `f64`.
+    Integer(HLIRType, Span),
+    /// The type must be `bool`, as it is used as a condition or as an operand
+    /// of a logical operator. Solved once literal types have been defaulted,
+    /// so that `let x = 1; if x {}` reports the condition rather than the
+    /// literal.
+    Bool(HLIRType, Span),
+    /// The type must be an integer or floating point type, as it is used in
+    /// arithmetic.
+    Numeric(HLIRType, Span),
+    /// The type must be a signed integer or floating point type, as it is
+    /// negated.
+    Signed(HLIRType, Span),
+    /// The type must be an integer type, as it is used with a bitwise
+    /// operator. Unlike `Integer`, this does not give the type a default.
+    Integral(HLIRType, Span),
+    /// The first type must be convertible to the second with `as`. Checked
+    /// once literal types have been defaulted.
+    Cast(HLIRType, HLIRType, Span),
+    /// The types of the operands and result of `+` or `-`. If the first
+    /// operand is a pointer, the second must be an integer offset and the
+    /// result is a pointer of the same type. Otherwise all three types must
+    /// be the same numeric type.
+    Additive(HLIRType, HLIRType, HLIRType, Span),
+    /// The type must be an integer type, as it is used with the given
+    /// wrapping or saturating operator.
+    IntegerArithmetic(HLIRType, &'static str, Span),
+    /// The type must be an integer type, as it is the type of the value
+    /// changed by the named atomic read-modify-write intrinsic.
+    Atomic(HLIRType, &'source str, Span),
+    /// The type must not be `void`, as it is the type of the value a pointer
+    /// that is dereferenced at the span points to.
+    Pointee(HLIRType, Span),
     Binding(&'source str, HLIRType),
 }
 
@@ -21,6 +64,25 @@ pub struct ZoidLoweringContext<'source> {
     pub substitution_map: HashMap<usize, HLIRType>,
     pub constraints: Vec<Constraint<'source>>,
     pub next_variable_id: usize,
+    /// Type variables that have already been reported, such as those given
+    /// to names that could not be resolved, so are not reported again if left
+    /// unresolved.
+    pub unresolved_variables: Vec<usize>,
+    /// Where each function is defined, for pointing at it in diagnostics.
+    pub function_spans: HashMap<&'source str, Span>,
+    /// The loops enclosing the statement being lowered, innermost last.
+    pub loops: Vec<Span>,
+    /// The variables in scope, with a map for each enclosing block,
+    /// innermost last. Later declarations in a scope shadow earlier ones.
+    pub scopes: Vec<HashMap<&'source str, (HLIRBindingId, HLIRType)>>,
+    pub next_binding_id: usize,
+    /// The type and value of each `const` that has not been evaluated yet.
+    pub constants: HashMap<&'source str, (HLIRType, Expression<'source>)>,
+    /// Where each `const` is declared, whether or not it could be evaluated.
+    pub constant_spans: HashMap<&'source str, Span>,
+    /// The constants being evaluated, outermost first, for detecting cycles.
+    pub evaluating: Vec<&'source str>,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl<'source> ZoidLoweringContext<'source> {
@@ -35,22 +97,54 @@ impl<'source> ZoidLoweringContext<'source> {
             substitution_map: HashMap::new(),
             constraints: Vec::new(),
             next_variable_id: 0,
+            unresolved_variables: Vec::new(),
+            function_spans: HashMap::new(),
+            loops: Vec::new(),
+            scopes: Vec::new(),
+            next_binding_id: 0,
+            constants: HashMap::new(),
+            constant_spans: HashMap::new(),
+            evaluating: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
+    /// Lowers the program to HLIR. Any errors are recorded in
+    /// `self.diagnostics`, in which case the returned program may be
+    /// incomplete and must not be passed on to code generation.
     pub fn lower(&mut self) -> HLIRProgram<'source> {
+        // Every prototype is known before any body is lowered, so functions
+        // can call functions defined after them.
+        for top_level in self.program.0.clone().iter() {
+            self.declare_top_level_expression(top_level);
+        }
+
+        // Constants are evaluated before any body is lowered, so references
+        // to them can be replaced by their values.
+        for top_level in self.program.0.clone().iter() {
+            if let TopLevelExpression::Constant { name, .. } = top_level {
+                self.evaluate_constant(name);
+            }
+        }
+
         for top_level in self.program.0.clone().iter() {
             self.lower_top_level_expression(top_level);
         }
 
-        self.solve_constraints()
+        let program = self.solve_constraints();
+
+        self.diagnostics
+            .sort_by_key(|diagnostic| diagnostic.primary_span().map(|span| span.start));
+
+        program
     }
 
     fn solve_constraints(&mut self) -> HLIRProgram<'source> {
         for constraint in self.constraints.clone().iter() {
+            #[allow(clippy::single_match)]
             match constraint {
-                Constraint::Equal(ty1, ty2) => {
-                    self.unify(&ty1, &ty2);
+                Constraint::Equal(ty1, ty2, span) => {
+                    self.unify(ty1, ty2, *span);
                 }
                 _ => (), // Constraint::Binding(name, ty) => {
                          //     self.substitution_map.insert(ty.var_id().unwrap(), *ty);
@@ -59,24 +153,103 @@ impl<'source> ZoidLoweringContext<'source> {
         }
         self.apply_substitutions();
 
+        // Whether `+` and `-` offset a pointer is only known once the type of
+        // their first operand is.
+        for constraint in self.constraints.clone().iter() {
+            #[allow(clippy::single_match)]
+            match constraint {
+                Constraint::Additive(lhs, rhs, result, span) => {
+                    self.unify_additive(lhs, rhs, result, *span)
+                }
+                _ => (),
+            }
+        }
+        self.apply_substitutions();
+
+        // Literals whose type is not otherwise constrained get a default type.
+        // Floats go first so that a type variable shared by a float and an
+        // integer literal becomes `f64` and the integer literal is reported.
+        for constraint in self.constraints.clone().iter() {
+            #[allow(clippy::single_match)]
+            match constraint {
+                Constraint::Float(ty, _) => self.default_type(ty, HLIRType::F64),
+                _ => (),
+            }
+        }
+        for constraint in self.constraints.clone().iter() {
+            #[allow(clippy::single_match)]
+            match constraint {
+                Constraint::Integer(ty, _) => self.default_type(ty, HLIRType::I32),
+                _ => (),
+            }
+        }
+
+        for constraint in self.constraints.clone().iter() {
+            #[allow(clippy::single_match)]
+            match constraint {
+                Constraint::Bool(ty, span) => self.unify_bool(ty, *span),
+                _ => (),
+            }
+        }
+
+        for constraint in self.constraints.clone().iter() {
+            match constraint {
+                Constraint::Float(ty, span) => self.check_float(ty, *span),
+                Constraint::Integer(ty, span) => self.check_integer(ty, *span),
+                Constraint::Numeric(ty, span) => self.check_numeric(ty, *span),
+                Constraint::Signed(ty, span) => self.check_signed(ty, *span),
+                Constraint::Integral(ty, span) => self.check_integral(ty, *span),
+                Constraint::Cast(from, to, span) => self.check_cast(from, to, *span),
+                Constraint::Additive(lhs, rhs, _, span) => self.check_additive(lhs, rhs, *span),
+                Constraint::IntegerArithmetic(ty, op, span) => {
+                    self.check_integer_arithmetic(ty, op, *span)
+                }
+                Constraint::Atomic(ty, intrinsic, span) => self.check_atomic(ty, intrinsic, *span),
+                Constraint::Pointee(ty, span) => self.check_pointee(ty, *span),
+                _ => (),
+            }
+        }
+
         self.constraints.clear();
 
         self.propagate_types()
     }
 
-    fn unify(&mut self, ty1: &HLIRType, ty2: &HLIRType) {
+    fn unify(&mut self, ty1: &HLIRType, ty2: &HLIRType, span: Span) {
+        if !self.try_unify(ty1, ty2) {
+            let ty1 = self.apply_substitution(ty1);
+            let ty2 = self.apply_substitution(ty2);
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot unify types `{}` and `{}`", ty1, ty2))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("expected `{}`, found `{}`", ty1, ty2)),
+            );
+        }
+    }
+
+    /// Unifies two types without reporting, returning whether they could be
+    /// unified. Pointers are unified through their pointee types, so that a
+    /// mismatch is reported with the pointer types as a whole.
+    fn try_unify(&mut self, ty1: &HLIRType, ty2: &HLIRType) -> bool {
         let ty1 = self.apply_substitution(ty1);
         let ty2 = self.apply_substitution(ty2);
 
         match (ty1, ty2) {
             (HLIRType::Var(id1), HLIRType::Var(id2)) => {
-                if id1 == id2 {
-                    return;
+                if id1 != id2 {
+                    self.substitution_map.insert(id1, HLIRType::Var(id2));
                 }
-                self.substitution_map.insert(id1, HLIRType::Var(id2));
+                true
             }
             (HLIRType::Var(id), ty) | (ty, HLIRType::Var(id)) => {
+                if Self::occurs(id, &ty) {
+                    return false;
+                }
                 self.substitution_map.insert(id, ty);
+                true
+            }
+            (HLIRType::Pointer(pointee1), HLIRType::Pointer(pointee2)) => {
+                self.try_unify(&pointee1, &pointee2)
             }
             // (HLIRType::Function(params1, ret1), HLIRType::Function(params2, ret2)) => {
             //     if params1.len() != params2.len() {
@@ -87,28 +260,232 @@ impl<'source> ZoidLoweringContext<'source> {
             //     }
             //     self.unify(ret1, ret2);
             // }
-            (ty1, ty2) => {
-                if ty1 != ty2 {
-                    panic!("Cannot unify types {:?} and {:?}", ty1, ty2);
+            (ty1, ty2) => ty1 == ty2,
+        }
+    }
+
+    /// Whether the type variable `id` appears in `ty`, which would make
+    /// binding it to `ty` create an infinite type such as `*_` = `**_`.
+    fn occurs(id: usize, ty: &HLIRType) -> bool {
+        match ty {
+            HLIRType::Var(var_id) => *var_id == id,
+            HLIRType::Pointer(pointee) => Self::occurs(id, pointee),
+            _ => false,
+        }
+    }
+
+    fn unify_additive(&mut self, lhs: &HLIRType, rhs: &HLIRType, result: &HLIRType, span: Span) {
+        if let HLIRType::Pointer(_) = self.apply_substitution(lhs) {
+            self.unify(lhs, result, span);
+        } else {
+            self.unify(lhs, rhs, span);
+            self.unify(lhs, result, span);
+        }
+    }
+
+    fn check_additive(&mut self, lhs: &HLIRType, rhs: &HLIRType, span: Span) {
+        if !matches!(self.apply_substitution(lhs), HLIRType::Pointer(_)) {
+            self.check_numeric(lhs, span);
+            return;
+        }
+
+        let rhs = self.apply_substitution(rhs);
+        if rhs.integer_bits().is_none() && !matches!(rhs, HLIRType::Var(_)) {
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot offset a pointer by `{}`", rhs))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("offset has type `{}`", rhs))
+                    .with_help("pointers can only be offset by integers"),
+            );
+        }
+    }
+
+    fn unify_bool(&mut self, ty: &HLIRType, span: Span) {
+        match self.apply_substitution(ty) {
+            HLIRType::Bool => (),
+            HLIRType::Var(_) => self.unify(&HLIRType::Bool, ty, span),
+            ty => {
+                let mut diagnostic = Diagnostic::error(format!("expected `bool`, found `{}`", ty))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("expected `bool`, found `{}`", ty));
+                if ty.integer_bits().is_some() || matches!(ty, HLIRType::F32 | HLIRType::F64) {
+                    diagnostic = diagnostic
+                        .with_help("numbers are not conditions, compare with `!= 0` instead");
+                } else if let HLIRType::Pointer(_) = ty {
+                    diagnostic = diagnostic
+                        .with_help("pointers are not conditions, compare with `!= null` instead");
                 }
+                self.diagnostics.push(diagnostic);
             }
         }
     }
 
+    fn check_numeric(&mut self, ty: &HLIRType, span: Span) {
+        let ty = self.apply_substitution(ty);
+
+        if matches!(ty, HLIRType::Bool | HLIRType::Void | HLIRType::Pointer(_)) {
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot use arithmetic on `{}`", ty))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("operands have type `{}`", ty))
+                    .with_help("arithmetic operators need integer or floating point operands"),
+            );
+        }
+    }
+
+    fn check_signed(&mut self, ty: &HLIRType, span: Span) {
+        let ty = self.apply_substitution(ty);
+
+        if !ty.is_signed_integer()
+            && !matches!(ty, HLIRType::F32 | HLIRType::F64 | HLIRType::Var(_))
+        {
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot negate a value of type `{}`", ty))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("operand has type `{}`", ty))
+                    .with_help("only signed integers and floating point values can be negated"),
+            );
+        }
+    }
+
+    fn check_integral(&mut self, ty: &HLIRType, span: Span) {
+        let ty = self.apply_substitution(ty);
+
+        if ty.integer_bits().is_none() && !matches!(ty, HLIRType::Var(_)) {
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot use bitwise operators on `{}`", ty))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("operand has type `{}`", ty))
+                    .with_help("bitwise operators need integer operands"),
+            );
+        }
+    }
+
+    fn check_integer_arithmetic(&mut self, ty: &HLIRType, op: &str, span: Span) {
+        let ty = self.apply_substitution(ty);
+
+        if ty.integer_bits().is_none() && !matches!(ty, HLIRType::Var(_)) {
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot use `{}` on `{}`", op, ty))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("operands have type `{}`", ty))
+                    .with_help("wrapping and saturating operators need integer operands"),
+            );
+        }
+    }
+
+    fn check_atomic(&mut self, ty: &HLIRType, intrinsic: &str, span: Span) {
+        let ty = self.apply_substitution(ty);
+
+        if ty.integer_bits().is_none() && !matches!(ty, HLIRType::Var(_)) {
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot use `{}` on `{}`", intrinsic, ty))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("value has type `{}`", ty))
+                    .with_help("atomic read-modify-write operations need integer operands"),
+            );
+        }
+    }
+
+    fn check_pointee(&mut self, ty: &HLIRType, span: Span) {
+        if self.apply_substitution(ty) == HLIRType::Void {
+            self.diagnostics.push(
+                Diagnostic::error("cannot dereference a pointer to `void`")
+                    .with_code(codes::DEREFERENCE_VOID)
+                    .with_primary(span, "this pointer has type `*void`")
+                    .with_help("cast it to a pointer to the type of the value, such as `*u8`"),
+            );
+        }
+    }
+
+    fn check_cast(&mut self, from: &HLIRType, to: &HLIRType, span: Span) {
+        let from = self.apply_substitution(from);
+
+        if !matches!(from, HLIRType::Var(_)) && !Self::is_valid_cast(&from, to) {
+            self.report_invalid_cast(&from, to, span);
+        }
+    }
+
+    /// Whether `as` can convert a value of type `from` to `to`. Numbers can
+    /// be converted to any other numeric type and `bool` to any integer type.
+    /// Pointers can be converted to other pointer types and to and from
+    /// integers.
+    fn is_valid_cast(from: &HLIRType, to: &HLIRType) -> bool {
+        let is_numeric = |ty: &HLIRType| ty.integer_bits().is_some() || ty.is_float();
+        match (from, to) {
+            (HLIRType::Bool, to) => *to == HLIRType::Bool || to.integer_bits().is_some(),
+            (HLIRType::Pointer(_), HLIRType::Pointer(_)) => true,
+            (HLIRType::Pointer(_), other) | (other, HLIRType::Pointer(_)) => {
+                other.integer_bits().is_some()
+            }
+            (from, to) => is_numeric(from) && is_numeric(to),
+        }
+    }
+
+    fn report_invalid_cast(&mut self, from: &HLIRType, to: &HLIRType, span: Span) {
+        let mut diagnostic = Diagnostic::error(format!("cannot cast `{}` as `{}`", from, to))
+            .with_code(codes::INVALID_CAST)
+            .with_primary(span, "invalid cast");
+        if *to == HLIRType::Bool && (from.integer_bits().is_some() || from.is_float()) {
+            diagnostic = diagnostic.with_help("compare with `!= 0` instead");
+        } else {
+            diagnostic = diagnostic.with_note(
+                "only numbers can be cast to other numeric types, `bool` to integer types, \
+                 and pointers to integers and other pointers",
+            );
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn check_float(&mut self, ty: &HLIRType, span: Span) {
+        let ty = self.apply_substitution(ty);
+
+        match ty {
+            HLIRType::F32 | HLIRType::F64 | HLIRType::Var(_) => (),
+            ty => self.diagnostics.push(
+                Diagnostic::error(format!("expected `{}`, found floating point literal", ty))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("expected `{}`", ty))
+                    .with_help("floating point literals can only have type `f32` or `f64`"),
+            ),
+        }
+    }
+
+    fn check_integer(&mut self, ty: &HLIRType, span: Span) {
+        let ty = self.apply_substitution(ty);
+
+        if ty.integer_bits().is_none() && !matches!(ty, HLIRType::Var(_)) {
+            self.diagnostics.push(
+                Diagnostic::error(format!("expected `{}`, found integer literal", ty))
+                    .with_code(codes::MISMATCHED_TYPES)
+                    .with_primary(span, format!("expected `{}`", ty))
+                    .with_help("integer literals can only have integer types"),
+            );
+        }
+    }
+
+    /// Gives a type variable that is still unresolved the type `default`.
+    fn default_type(&mut self, ty: &HLIRType, default: HLIRType) {
+        if let HLIRType::Var(id) = self.apply_substitution(ty) {
+            self.substitution_map.insert(id, default);
+        }
+    }
+
     fn apply_substitution(&self, ty: &HLIRType) -> HLIRType {
         match ty {
             HLIRType::Var(id) => {
                 if let Some(substituted_ty) = self.substitution_map.get(id) {
                     self.apply_substitution(substituted_ty)
                 } else {
-                    *ty
+                    ty.clone()
                 }
             }
+            HLIRType::Pointer(pointee) => HLIRType::pointer_to(self.apply_substitution(pointee)),
             // HLIRType::Function(params, ret) => HLIRType::Function(
             //     params.iter().map(|p| self.apply_substitution(p)).collect(),
             //     Box::new(self.apply_substitution(ret)),
             // ),
-            _ => *ty,
+            _ => ty.clone(),
         }
     }
 
@@ -119,6 +496,719 @@ impl<'source> ZoidLoweringContext<'source> {
         }
     }
 
+    fn fresh_type_variable(&mut self) -> HLIRType {
+        let id = self.next_variable_id;
+        self.next_variable_id += 1;
+        HLIRType::Var(id)
+    }
+
+    fn declare_top_level_expression(&mut self, top_level: &TopLevelExpression<'source>) {
+        match top_level {
+            TopLevelExpression::Function {
+                name,
+                parameters,
+                return_type,
+                span,
+                ..
+            } => {
+                self.declare_function(name, parameters, return_type, false, *span);
+            }
+            TopLevelExpression::ExternFunction {
+                abi,
+                name,
+                parameters,
+                return_type,
+                variadic,
+                span,
+            } => {
+                if *abi != "C" {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!("unsupported ABI `\"{}\"`", abi))
+                            .with_code(codes::UNSUPPORTED_ABI)
+                            .with_primary(*span, "declared here")
+                            .with_help("only `extern \"C\"` functions are supported"),
+                    );
+                }
+                self.declare_function(name, parameters, return_type, *variadic, *span);
+            }
+            TopLevelExpression::Constant {
+                name,
+                ty,
+                value,
+                span,
+            } => {
+                if let Some(previous) = self.constant_spans.get(name) {
+                    self.report_duplicate_definition("constant", name, *previous, *span);
+                    return;
+                }
+                let hlir_ty = self.lower_type(ty);
+                self.constants.insert(name, (hlir_ty, value.clone()));
+                self.constant_spans.insert(name, *span);
+            }
+            // The driver adds the imported declarations to the program.
+            TopLevelExpression::CImport { .. } => (),
+        }
+    }
+
+    fn declare_function(
+        &mut self,
+        name: &'source str,
+        parameters: &[Parameter<'source>],
+        return_type: &Option<Type>,
+        variadic: bool,
+        span: Span,
+    ) {
+        if let Some(previous) = self.function_spans.get(name) {
+            self.report_duplicate_definition("function", name, *previous, span);
+            return;
+        }
+
+        let parameter_types = parameters
+            .iter()
+            .map(|parameter| self.lower_type(&parameter.ty))
+            .collect();
+        let hlir_return_type = match return_type {
+            Some(ty) => self.lower_type(ty),
+            None => HLIRType::Void,
+        };
+
+        self.hlir_program.prototypes.insert(
+            name,
+            HLIRPrototype {
+                parameters: parameter_types,
+                return_type: hlir_return_type,
+                variadic,
+            },
+        );
+        self.function_spans.insert(name, span);
+    }
+
+    /// Reports a second definition of `name`, which is ignored in favour of
+    /// the first.
+    fn report_duplicate_definition(&mut self, kind: &str, name: &str, previous: Span, span: Span) {
+        self.diagnostics.push(
+            Diagnostic::error(format!("the {} `{}` is defined multiple times", kind, name))
+                .with_code(codes::DUPLICATE_DEFINITION)
+                .with_primary(span, format!("`{}` redefined here", name))
+                .with_secondary(previous, format!("previous definition of `{}` here", name)),
+        );
+    }
+
+    /// Evaluates the constant `name` and any constants it uses, recording
+    /// its value in the program's globals. Returns `None` if the constant
+    /// could not be evaluated, which has already been reported.
+    fn evaluate_constant(&mut self, name: &'source str) -> Option<(HLIRType, HLIRConstant)> {
+        if let Some(value) = self.hlir_program.globals.get(name) {
+            return Some(value.clone());
+        }
+
+        let (ty, value) = self.constants.remove(name)?;
+        self.evaluating.push(name);
+        let result = self.evaluate_constant_expression(&value, &ty);
+        self.evaluating.pop();
+
+        let result = result?;
+        self.hlir_program.globals.insert(name, (ty.clone(), result));
+        Some((ty, result))
+    }
+
+    /// Evaluates an expression of type `ty` in the constant being evaluated.
+    fn evaluate_constant_expression(
+        &mut self,
+        expression: &Expression<'source>,
+        ty: &HLIRType,
+    ) -> Option<HLIRConstant> {
+        match expression {
+            Expression::Literal(literal, span) => self.evaluate_literal(literal, ty, false, *span),
+            Expression::Variable(name, span) => {
+                if !self.constant_spans.contains_key(name) {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!("cannot find value `{}` in this scope", name))
+                            .with_code(codes::UNRESOLVED_NAME)
+                            .with_primary(*span, "not found in this scope")
+                            .with_note("constants can only refer to other constants"),
+                    );
+                    return None;
+                }
+
+                if let Some(start) = self.evaluating.iter().position(|c| c == name) {
+                    self.report_constant_cycle(start, *span);
+                    return None;
+                }
+
+                let (found, value) = self.evaluate_constant(name)?;
+                if found != *ty {
+                    self.unify(ty, &found, *span);
+                    return None;
+                }
+                Some(value)
+            }
+            Expression::BinaryOperation { lhs, op, rhs, span } => match op {
+                BinaryOperator::And | BinaryOperator::Or => {
+                    if *ty != HLIRType::Bool {
+                        self.unify_bool(ty, *span);
+                        return None;
+                    }
+                    let lhs = self.evaluate_constant_expression(lhs, ty);
+                    let rhs = self.evaluate_constant_expression(rhs, ty);
+                    let (HLIRConstant::Bool(lhs), HLIRConstant::Bool(rhs)) = (lhs?, rhs?) else {
+                        unreachable!("operands of logical operators are `bool`");
+                    };
+                    Some(HLIRConstant::Bool(match op {
+                        BinaryOperator::And => lhs && rhs,
+                        _ => lhs || rhs,
+                    }))
+                }
+                BinaryOperator::Eq
+                | BinaryOperator::Ne
+                | BinaryOperator::Lt
+                | BinaryOperator::Le
+                | BinaryOperator::Gt
+                | BinaryOperator::Ge => {
+                    self.report_unsupported_constant("comparisons", *span);
+                    None
+                }
+                BinaryOperator::WrappingAdd
+                | BinaryOperator::WrappingSub
+                | BinaryOperator::WrappingMul
+                | BinaryOperator::SaturatingAdd
+                | BinaryOperator::SaturatingSub
+                | BinaryOperator::SaturatingMul => {
+                    if ty.integer_bits().is_none() {
+                        self.check_integer_arithmetic(ty, Self::operator_symbol(*op), *span);
+                        return None;
+                    }
+                    let lhs = self.evaluate_constant_expression(lhs, ty);
+                    let rhs = self.evaluate_constant_expression(rhs, ty);
+                    Self::evaluate_wrapping_or_saturating(*op, lhs?, rhs?, ty)
+                }
+                BinaryOperator::BitAnd
+                | BinaryOperator::BitOr
+                | BinaryOperator::BitXor
+                | BinaryOperator::Shl
+                | BinaryOperator::Shr => {
+                    if ty.integer_bits().is_none() {
+                        self.check_integral(ty, *span);
+                        return None;
+                    }
+                    let lhs = self.evaluate_constant_expression(lhs, ty);
+                    let rhs = self.evaluate_constant_expression(rhs, ty);
+                    self.evaluate_bitwise(*op, lhs?, rhs?, ty, *span)
+                }
+                _ => {
+                    if let HLIRType::Pointer(_) = ty {
+                        self.report_unsupported_constant("pointer arithmetic", *span);
+                        return None;
+                    }
+                    if matches!(ty, HLIRType::Bool | HLIRType::Void) {
+                        self.check_numeric(ty, *span);
+                        return None;
+                    }
+                    let lhs = self.evaluate_constant_expression(lhs, ty);
+                    let rhs = self.evaluate_constant_expression(rhs, ty);
+                    self.evaluate_arithmetic(*op, lhs?, rhs?, ty, *span)
+                }
+            },
+            Expression::UnaryOperation { op, operand, span } => match op {
+                UnaryOperator::Neg => {
+                    if !ty.is_signed_integer() && !matches!(ty, HLIRType::F32 | HLIRType::F64) {
+                        self.check_signed(ty, *span);
+                        return None;
+                    }
+                    if let Expression::Literal(literal, literal_span) = operand.as_ref() {
+                        return self.evaluate_literal(literal, ty, true, *literal_span);
+                    }
+                    match self.evaluate_constant_expression(operand, ty)? {
+                        HLIRConstant::Float(value) => Some(HLIRConstant::Float(-value)),
+                        HLIRConstant::Integer(value) => {
+                            let min = -(ty.integer_max()? as i128) - 1;
+                            if value as i128 == min {
+                                self.report_constant_evaluation(
+                                    *span,
+                                    "attempt to negate with overflow",
+                                    Some(format!("the result does not fit in the type `{}`", ty)),
+                                );
+                                return None;
+                            }
+                            Some(HLIRConstant::Integer((value as i128).wrapping_neg() as u128))
+                        }
+                        HLIRConstant::Bool(_) | HLIRConstant::Null => {
+                            unreachable!("the operand of `-` is numeric")
+                        }
+                    }
+                }
+                UnaryOperator::BitNot => {
+                    if ty.integer_bits().is_none() {
+                        self.check_integral(ty, *span);
+                        return None;
+                    }
+                    match self.evaluate_constant_expression(operand, ty)? {
+                        // Signed values are stored sign extended, so only
+                        // unsigned values need the bits above their width
+                        // cleared.
+                        HLIRConstant::Integer(value) if ty.is_signed_integer() => {
+                            Some(HLIRConstant::Integer(!value))
+                        }
+                        HLIRConstant::Integer(value) => {
+                            Some(HLIRConstant::Integer(!value & ty.integer_max()?))
+                        }
+                        _ => unreachable!("the operand of `~` is an integer"),
+                    }
+                }
+                UnaryOperator::Not => {
+                    if *ty != HLIRType::Bool {
+                        self.unify_bool(ty, *span);
+                        return None;
+                    }
+                    match self.evaluate_constant_expression(operand, ty)? {
+                        HLIRConstant::Bool(value) => Some(HLIRConstant::Bool(!value)),
+                        _ => unreachable!("the operand of `not` is `bool`"),
+                    }
+                }
+            },
+            Expression::Call { span, .. } => {
+                self.report_unsupported_constant("function calls", *span);
+                None
+            }
+            Expression::Cast {
+                value,
+                ty: cast_ty,
+                span,
+            } => {
+                let to = self.lower_type(cast_ty);
+                if to != *ty {
+                    self.unify(ty, &to, *span);
+                    return None;
+                }
+
+                let from = self.constant_expression_type(value).unwrap_or_else(|| {
+                    if Self::has_float_literal(value) {
+                        HLIRType::F64
+                    } else {
+                        HLIRType::I32
+                    }
+                });
+                if !Self::is_valid_cast(&from, &to) {
+                    self.report_invalid_cast(&from, &to, *span);
+                    return None;
+                }
+                // Only `null` is known to be a valid address in a constant.
+                if matches!(from, HLIRType::Pointer(_)) != matches!(to, HLIRType::Pointer(_)) {
+                    self.report_unsupported_constant("casts between pointers and integers", *span);
+                    return None;
+                }
+
+                let value = self.evaluate_constant_expression(value, &from)?;
+                self.evaluate_cast(value, &from, &to, *span)
+            }
+            Expression::AddressOf { span, .. } => {
+                self.report_unsupported_constant("addresses", *span);
+                None
+            }
+            Expression::Dereference { span, .. } | Expression::Index { span, .. } => {
+                self.report_unsupported_constant("dereferences", *span);
+                None
+            }
+        }
+    }
+
+    /// The type of an expression in a constant, or `None` if it is only
+    /// decided by unsuffixed literals, which take their default type. Used
+    /// for the operands of casts, which do not get a type from the constant.
+    fn constant_expression_type(&mut self, expression: &Expression<'source>) -> Option<HLIRType> {
+        match expression {
+            Expression::Literal(Literal::Integer(_, Some(suffix)), _) => {
+                Some(self.lower_type(suffix))
+            }
+            Expression::Literal(Literal::Integer(_, None) | Literal::Float(_), _) => None,
+            Expression::Literal(Literal::Bool(_), _) => Some(HLIRType::Bool),
+            Expression::Literal(Literal::Null, _) => Some(HLIRType::pointer_to(HLIRType::Void)),
+            Expression::Variable(name, _) => match self.hlir_program.globals.get(name) {
+                Some((ty, _)) => Some(ty.clone()),
+                None => self.constants.get(name).map(|(ty, _)| ty.clone()),
+            },
+            Expression::BinaryOperation { lhs, op, rhs, .. } => match op {
+                BinaryOperator::And
+                | BinaryOperator::Or
+                | BinaryOperator::Eq
+                | BinaryOperator::Ne
+                | BinaryOperator::Lt
+                | BinaryOperator::Le
+                | BinaryOperator::Gt
+                | BinaryOperator::Ge => Some(HLIRType::Bool),
+                BinaryOperator::Shl | BinaryOperator::Shr => self.constant_expression_type(lhs),
+                _ => self
+                    .constant_expression_type(lhs)
+                    .or_else(|| self.constant_expression_type(rhs)),
+            },
+            Expression::UnaryOperation { op, operand, .. } => match op {
+                UnaryOperator::Not => Some(HLIRType::Bool),
+                UnaryOperator::Neg | UnaryOperator::BitNot => {
+                    self.constant_expression_type(operand)
+                }
+            },
+            Expression::Call { .. }
+            | Expression::AddressOf { .. }
+            | Expression::Dereference { .. }
+            | Expression::Index { .. } => None,
+            Expression::Cast { ty, .. } => Some(self.lower_type(ty)),
+        }
+    }
+
+    /// Whether the type of an expression is decided by a float literal
+    /// rather than an integer one, when `constant_expression_type` gives
+    /// `None`.
+    fn has_float_literal(expression: &Expression<'source>) -> bool {
+        match expression {
+            Expression::Literal(literal, _) => matches!(literal, Literal::Float(_)),
+            Expression::BinaryOperation { lhs, rhs, .. } => {
+                Self::has_float_literal(lhs) || Self::has_float_literal(rhs)
+            }
+            Expression::UnaryOperation { operand, .. } => Self::has_float_literal(operand),
+            _ => false,
+        }
+    }
+
+    /// Converts a constant of type `from` to `to`, which must be a valid
+    /// cast. Floats that do not fit in an integer type are reported, as they
+    /// have no defined value at run time.
+    fn evaluate_cast(
+        &mut self,
+        value: HLIRConstant,
+        from: &HLIRType,
+        to: &HLIRType,
+        span: Span,
+    ) -> Option<HLIRConstant> {
+        let value = match value {
+            HLIRConstant::Integer(value) if to.is_float() => {
+                let value = if from.is_signed_integer() {
+                    value as i128 as f64
+                } else {
+                    value as f64
+                };
+                HLIRConstant::Float(value)
+            }
+            HLIRConstant::Integer(value) => HLIRConstant::Integer(to.wrap_integer(value)),
+            HLIRConstant::Float(value) if to.is_float() => HLIRConstant::Float(value),
+            HLIRConstant::Float(value) => {
+                let truncated = value.trunc();
+                let max = to.integer_max()? as f64;
+                let min = if to.is_signed_integer() {
+                    -max - 1.0
+                } else {
+                    0.0
+                };
+                // `max + 1` is a power of two, so is exact as a float even
+                // when `max` is not.
+                if value.is_nan() || truncated < min || truncated >= max + 1.0 {
+                    self.report_constant_evaluation(
+                        span,
+                        &format!("`{}` does not fit in `{}`", value, to),
+                        Some(format!(
+                            "casting a float outside the range of `{}` has no defined result",
+                            to
+                        )),
+                    );
+                    return None;
+                }
+                if to.is_signed_integer() {
+                    HLIRConstant::Integer(truncated as i128 as u128)
+                } else {
+                    HLIRConstant::Integer(truncated as u128)
+                }
+            }
+            HLIRConstant::Bool(value) if *to == HLIRType::Bool => HLIRConstant::Bool(value),
+            HLIRConstant::Bool(value) => HLIRConstant::Integer(value as u128),
+            HLIRConstant::Null => HLIRConstant::Null,
+        };
+
+        // An `f32` result is rounded to the nearest `f32`.
+        Some(match value {
+            HLIRConstant::Float(value) if *to == HLIRType::F32 => {
+                HLIRConstant::Float(value as f32 as f64)
+            }
+            value => value,
+        })
+    }
+
+    /// Evaluates a literal of type `ty`, which is negated if it is the
+    /// operand of `-`.
+    fn evaluate_literal(
+        &mut self,
+        literal: &Literal<'source>,
+        ty: &HLIRType,
+        negative: bool,
+        span: Span,
+    ) -> Option<HLIRConstant> {
+        match literal {
+            Literal::Integer(value, suffix) => {
+                if let Some(suffix) = suffix {
+                    let suffix = self.lower_type(suffix);
+                    if suffix != *ty {
+                        self.unify(ty, &suffix, span);
+                        return None;
+                    }
+                }
+                self.check_integer(ty, span);
+                ty.integer_bits()?;
+                if !self.check_integer_literal(*value, ty, negative, span) {
+                    return None;
+                }
+                Some(HLIRConstant::Integer(if negative {
+                    value.wrapping_neg()
+                } else {
+                    *value
+                }))
+            }
+            Literal::Float(value) => {
+                self.check_float(ty, span);
+                let value: f64 = match ty {
+                    HLIRType::F32 => value.replace('_', "").parse::<f32>().ok()?.into(),
+                    HLIRType::F64 => value.replace('_', "").parse().ok()?,
+                    _ => return None,
+                };
+                Some(HLIRConstant::Float(if negative { -value } else { value }))
+            }
+            Literal::Bool(value) => {
+                if *ty != HLIRType::Bool {
+                    self.unify(ty, &HLIRType::Bool, span);
+                    return None;
+                }
+                Some(HLIRConstant::Bool(*value))
+            }
+            Literal::Null => {
+                if let HLIRType::Pointer(_) = ty {
+                    return Some(HLIRConstant::Null);
+                }
+                self.diagnostics.push(
+                    Diagnostic::error(format!("expected `{}`, found `null`", ty))
+                        .with_code(codes::MISMATCHED_TYPES)
+                        .with_primary(span, format!("expected `{}`", ty))
+                        .with_help("`null` can only have a pointer type"),
+                );
+                None
+            }
+        }
+    }
+
+    /// Applies a wrapping or saturating operator to two integer constants of
+    /// type `ty`. Up to 64 bits, the `i128` result is exact before it is
+    /// wrapped or clamped, and for 128-bit types `i128` and `u128` already
+    /// saturate at the type's limits.
+    fn evaluate_wrapping_or_saturating(
+        op: BinaryOperator,
+        lhs: HLIRConstant,
+        rhs: HLIRConstant,
+        ty: &HLIRType,
+    ) -> Option<HLIRConstant> {
+        let (HLIRConstant::Integer(lhs), HLIRConstant::Integer(rhs)) = (lhs, rhs) else {
+            unreachable!("operands of wrapping and saturating operators are integers");
+        };
+
+        let max = ty.integer_max()?;
+        let value = match op {
+            BinaryOperator::WrappingAdd => ty.wrap_integer(lhs.wrapping_add(rhs)),
+            BinaryOperator::WrappingSub => ty.wrap_integer(lhs.wrapping_sub(rhs)),
+            BinaryOperator::WrappingMul => ty.wrap_integer(lhs.wrapping_mul(rhs)),
+            op if ty.is_signed_integer() => {
+                let (lhs, rhs) = (lhs as i128, rhs as i128);
+                let value = match op {
+                    BinaryOperator::SaturatingAdd => lhs.saturating_add(rhs),
+                    BinaryOperator::SaturatingSub => lhs.saturating_sub(rhs),
+                    _ => lhs.saturating_mul(rhs),
+                };
+                value.clamp(-(max as i128) - 1, max as i128) as u128
+            }
+            op => match op {
+                BinaryOperator::SaturatingAdd => lhs.saturating_add(rhs),
+                BinaryOperator::SaturatingSub => lhs.saturating_sub(rhs),
+                _ => lhs.saturating_mul(rhs),
+            }
+            .min(max),
+        };
+        Some(HLIRConstant::Integer(value))
+    }
+
+    /// Applies an arithmetic operator to two constants of type `ty`,
+    /// reporting results that do not fit in `ty` and division by zero.
+    fn evaluate_arithmetic(
+        &mut self,
+        op: BinaryOperator,
+        lhs: HLIRConstant,
+        rhs: HLIRConstant,
+        ty: &HLIRType,
+        span: Span,
+    ) -> Option<HLIRConstant> {
+        let (lhs, rhs) = match (lhs, rhs) {
+            (HLIRConstant::Float(lhs), HLIRConstant::Float(rhs)) => {
+                let value = match op {
+                    BinaryOperator::Add => lhs + rhs,
+                    BinaryOperator::Sub => lhs - rhs,
+                    BinaryOperator::Mul => lhs * rhs,
+                    BinaryOperator::Div => lhs / rhs,
+                    BinaryOperator::Rem => lhs % rhs,
+                    _ => unreachable!("not an arithmetic operator"),
+                };
+                return Some(HLIRConstant::Float(if *ty == HLIRType::F32 {
+                    value as f32 as f64
+                } else {
+                    value
+                }));
+            }
+            (HLIRConstant::Integer(lhs), HLIRConstant::Integer(rhs)) => (lhs, rhs),
+            _ => unreachable!("operands of arithmetic have the same numeric type"),
+        };
+
+        if rhs == 0 && matches!(op, BinaryOperator::Div | BinaryOperator::Rem) {
+            let label = match op {
+                BinaryOperator::Div => "attempt to divide by zero",
+                _ => "attempt to calculate the remainder with a divisor of zero",
+            };
+            self.report_constant_evaluation(span, label, None);
+            return None;
+        }
+
+        let max = ty.integer_max()?;
+        let value = if ty.is_signed_integer() {
+            let (lhs, rhs) = (lhs as i128, rhs as i128);
+            let min = -(max as i128) - 1;
+            match op {
+                BinaryOperator::Add => lhs.checked_add(rhs),
+                BinaryOperator::Sub => lhs.checked_sub(rhs),
+                BinaryOperator::Mul => lhs.checked_mul(rhs),
+                BinaryOperator::Div => lhs.checked_div(rhs),
+                BinaryOperator::Rem => lhs.checked_rem(rhs),
+                _ => unreachable!("not an arithmetic operator"),
+            }
+            .filter(|value| (min..=max as i128).contains(value))
+            .map(|value| value as u128)
+        } else {
+            match op {
+                BinaryOperator::Add => lhs.checked_add(rhs),
+                BinaryOperator::Sub => lhs.checked_sub(rhs),
+                BinaryOperator::Mul => lhs.checked_mul(rhs),
+                BinaryOperator::Div => lhs.checked_div(rhs),
+                BinaryOperator::Rem => lhs.checked_rem(rhs),
+                _ => unreachable!("not an arithmetic operator"),
+            }
+            .filter(|value| *value <= max)
+        };
+
+        if value.is_none() {
+            let label = match op {
+                BinaryOperator::Add => "attempt to add with overflow",
+                BinaryOperator::Sub => "attempt to subtract with overflow",
+                BinaryOperator::Mul => "attempt to multiply with overflow",
+                BinaryOperator::Div => "attempt to divide with overflow",
+                _ => "attempt to calculate the remainder with overflow",
+            };
+            self.report_constant_evaluation(
+                span,
+                label,
+                Some(format!("the result does not fit in the type `{}`", ty)),
+            );
+        }
+
+        value.map(HLIRConstant::Integer)
+    }
+
+    /// Applies a bitwise or shift operator to two integer constants of type
+    /// `ty`, reporting shifts by at least the width of `ty`.
+    fn evaluate_bitwise(
+        &mut self,
+        op: BinaryOperator,
+        lhs: HLIRConstant,
+        rhs: HLIRConstant,
+        ty: &HLIRType,
+        span: Span,
+    ) -> Option<HLIRConstant> {
+        let (HLIRConstant::Integer(lhs), HLIRConstant::Integer(rhs)) = (lhs, rhs) else {
+            unreachable!("operands of bitwise operators are integers");
+        };
+
+        let value = match op {
+            BinaryOperator::BitAnd => lhs & rhs,
+            BinaryOperator::BitOr => lhs | rhs,
+            BinaryOperator::BitXor => lhs ^ rhs,
+            _ => {
+                let bits = ty.integer_bits()?;
+                if (ty.is_signed_integer() && (rhs as i128) < 0) || rhs >= bits as u128 {
+                    let label = match op {
+                        BinaryOperator::Shl => "attempt to shift left with overflow",
+                        _ => "attempt to shift right with overflow",
+                    };
+                    self.report_constant_evaluation(
+                        span,
+                        label,
+                        Some(format!(
+                            "a value of type `{}` can only be shifted by `0..{}`",
+                            ty, bits
+                        )),
+                    );
+                    return None;
+                }
+
+                match op {
+                    BinaryOperator::Shl => ty.wrap_integer(lhs << rhs),
+                    _ if ty.is_signed_integer() => ((lhs as i128) >> rhs) as u128,
+                    _ => lhs >> rhs,
+                }
+            }
+        };
+
+        Some(HLIRConstant::Integer(value))
+    }
+
+    fn report_constant_evaluation(&mut self, span: Span, label: &str, note: Option<String>) {
+        let name = self
+            .evaluating
+            .last()
+            .expect("a constant is being evaluated");
+        let mut diagnostic = Diagnostic::error(format!("evaluation of constant `{}` failed", name))
+            .with_code(codes::CONSTANT_EVALUATION)
+            .with_primary(span, label);
+        if let Some(note) = note {
+            diagnostic = diagnostic.with_note(note);
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn report_unsupported_constant(&mut self, what: &str, span: Span) {
+        self.report_constant_evaluation(
+            span,
+            &format!("{} are not supported in constants", what),
+            Some(
+                "constants can only use literals, arithmetic, bitwise and logical operators \
+                 and other constants"
+                    .to_string(),
+            ),
+        );
+    }
+
+    /// Reports that evaluating `self.evaluating[start]` requires its own
+    /// value, as it is used at `span` by the constants after it.
+    fn report_constant_cycle(&mut self, start: usize, span: Span) {
+        let cycle = &self.evaluating[start..];
+        let mut diagnostic = Diagnostic::error(format!(
+            "cycle detected when evaluating constant `{}`",
+            cycle[0]
+        ))
+        .with_code(codes::CONSTANT_CYCLE)
+        .with_primary(self.constant_spans[cycle[0]], "")
+        .with_secondary(span, format!("`{}` is used here", cycle[0]));
+        for name in &cycle[1..] {
+            diagnostic = diagnostic.with_note(format!(
+                "...which requires evaluating constant `{}`...",
+                name
+            ));
+        }
+        diagnostic = diagnostic.with_note(format!(
+            "...which again requires evaluating constant `{}`, completing the cycle",
+            cycle[0]
+        ));
+        self.diagnostics.push(diagnostic);
+    }
+
     fn lower_top_level_expression(&mut self, top_level: &TopLevelExpression<'source>) {
         match top_level {
             TopLevelExpression::Function {
@@ -126,7 +1216,11 @@ impl<'source> ZoidLoweringContext<'source> {
                 parameters,
                 return_type,
                 body,
+                span,
             } => {
+                // Parameters are in a scope around the body, so the body can
+                // shadow them.
+                self.scopes.push(HashMap::new());
                 let mut hlir_parameters = Vec::new();
                 for parameter in parameters {
                     hlir_parameters.push(self.lower_parameter(parameter));
@@ -136,21 +1230,26 @@ impl<'source> ZoidLoweringContext<'source> {
                     None => HLIRType::Void,
                 };
 
-                let hlir_prototype = (
-                    hlir_parameters.iter().map(|p| p.ty).collect(),
-                    hlir_return_type,
-                );
-                self.hlir_program.prototypes.insert(name, hlir_prototype);
-
-                let mut named_values = HashMap::new();
+                let hlir_body = self.lower_block(body, &hlir_return_type);
+                self.scopes.pop();
 
-                let mut hlir_body = Vec::new();
-                for statement in body {
-                    hlir_body.push(self.lower_statement(
-                        statement,
-                        &mut named_values,
-                        &hlir_return_type,
-                    ));
+                if hlir_return_type != HLIRType::Void && !Self::always_returns(&hlir_body) {
+                    // The span of a function ends with its closing brace.
+                    let end = Span::new(span.file, span.end - 1, span.end);
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "function `{}` can reach its end without returning a value",
+                            name
+                        ))
+                        .with_code(codes::MISSING_RETURN)
+                        .with_primary(
+                            end,
+                            format!(
+                                "expected a `return` of type `{}` before here",
+                                hlir_return_type
+                            ),
+                        ),
+                    );
                 }
 
                 self.hlir_program.functions.push(HLIRFunction {
@@ -158,18 +1257,52 @@ impl<'source> ZoidLoweringContext<'source> {
                     parameters: hlir_parameters,
                     return_type: hlir_return_type,
                     body: hlir_body,
+                    span: *span,
                 });
             }
+            // Extern functions have no body, and are only needed as
+            // prototypes. Constants have already been evaluated, and
+            // references to them are replaced by their values.
+            TopLevelExpression::ExternFunction { .. }
+            | TopLevelExpression::CImport { .. }
+            | TopLevelExpression::Constant { .. } => (),
         }
     }
 
     fn lower_parameter(&mut self, parameter: &Parameter<'source>) -> HLIRParameter<'source> {
+        let ty = self.lower_type(&parameter.ty);
         HLIRParameter {
             name: parameter.name,
-            ty: self.lower_type(&parameter.ty),
+            binding: self.declare_variable(parameter.name, ty.clone()),
+            ty,
+            span: parameter.span,
         }
     }
 
+    /// Adds a variable to the innermost scope, shadowing any variable of the
+    /// same name.
+    fn declare_variable(&mut self, name: &'source str, ty: HLIRType) -> HLIRBindingId {
+        let binding = self.fresh_binding_id();
+        self.scopes
+            .last_mut()
+            .expect("variables are declared inside a function")
+            .insert(name, (binding, ty));
+        binding
+    }
+
+    fn lookup_variable(&self, name: &str) -> Option<(HLIRBindingId, HLIRType)> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+    }
+
+    fn fresh_binding_id(&mut self) -> HLIRBindingId {
+        let id = self.next_binding_id;
+        self.next_binding_id += 1;
+        HLIRBindingId(id)
+    }
+
     fn lower_type(&mut self, ty: &Type) -> HLIRType {
         match ty {
             Type::I8 => HLIRType::I8,
@@ -184,98 +1317,684 @@ impl<'source> ZoidLoweringContext<'source> {
             Type::U128 => HLIRType::U128,
             Type::F32 => HLIRType::F32,
             Type::F64 => HLIRType::F64,
+            Type::Bool => HLIRType::Bool,
             Type::Void => HLIRType::Void,
+            Type::Pointer(pointee) => {
+                let pointee = self.lower_type(pointee);
+                HLIRType::pointer_to(pointee)
+            }
         }
     }
 
+    fn lower_block(
+        &mut self,
+        block: &[Statement<'source>],
+        return_type: &HLIRType,
+    ) -> Vec<HLIRStatement<'source>> {
+        self.scopes.push(HashMap::new());
+        let block = block
+            .iter()
+            .map(|statement| self.lower_statement(statement, return_type))
+            .collect();
+        self.scopes.pop();
+        block
+    }
+
+    /// Whether every path through the block ends in a `return`.
+    fn always_returns(block: &[HLIRStatement<'source>]) -> bool {
+        block.iter().any(|statement| match statement {
+            HLIRStatement::Return(..) => true,
+            HLIRStatement::If {
+                then_branch,
+                else_branch: Some(else_branch),
+                ..
+            } => Self::always_returns(then_branch) && Self::always_returns(else_branch),
+            _ => false,
+        })
+    }
+
     fn lower_statement(
         &mut self,
         statement: &Statement<'source>,
-        named_values: &mut HashMap<&'source str, HLIRType>,
         return_type: &HLIRType,
     ) -> HLIRStatement<'source> {
         match statement {
-            Statement::VariableDeclaration { name, ty, value } => {
+            Statement::VariableDeclaration {
+                name,
+                ty,
+                value,
+                span,
+            } => {
                 let hlir_ty = match ty {
-                    Some(ty) => self.lower_type(ty),
-                    None => {
-                        let id = self.next_variable_id;
-                        self.next_variable_id += 1;
-                        HLIRType::Var(id)
-                    }
+                    Some(ty) => self.lower_type(ty),
+                    None => self.fresh_type_variable(),
                 };
-                let hlir_value = self.lower_expression(value, named_values);
-                self.constraints
-                    .push(Constraint::Equal(hlir_ty, hlir_value.ty()));
-                named_values.insert(name, hlir_ty);
+                let hlir_value = self.lower_expression(value);
+                self.constraints.push(Constraint::Equal(
+                    hlir_ty.clone(),
+                    hlir_value.ty(),
+                    hlir_value.span(),
+                ));
                 HLIRStatement::VariableDeclaration {
                     name,
+                    binding: self.declare_variable(name, hlir_ty.clone()),
                     ty: hlir_ty,
                     value: hlir_value,
+                    span: *span,
                 }
             }
-            Statement::Return(ref value) => match value {
+            Statement::Return(ref value, span) => match value {
                 Some(value) => {
-                    let hlir_value = self.lower_expression(value, named_values);
+                    let hlir_value = self.lower_expression(value);
 
-                    self.constraints
-                        .push(Constraint::Equal(hlir_value.ty(), *return_type));
+                    self.constraints.push(Constraint::Equal(
+                        return_type.clone(),
+                        hlir_value.ty(),
+                        hlir_value.span(),
+                    ));
 
-                    HLIRStatement::Return(Some(hlir_value))
+                    HLIRStatement::Return(Some(hlir_value), *span)
                 }
                 None => {
-                    if *return_type == HLIRType::Void {
-                        HLIRStatement::Return(None)
-                    } else {
-                        panic!("Return type does not match function return type");
+                    if *return_type != HLIRType::Void {
+                        self.diagnostics.push(
+                            Diagnostic::error("`return` without a value in a non-`void` function")
+                                .with_code(codes::MISSING_RETURN_VALUE)
+                                .with_primary(
+                                    *span,
+                                    format!("expected a value of type `{}`", return_type),
+                                ),
+                        );
                     }
+                    HLIRStatement::Return(None, *span)
                 }
             },
+            Statement::Expression(expression, span) => {
+                HLIRStatement::Expression(self.lower_expression(expression), *span)
+            }
+            Statement::Assignment {
+                target,
+                op,
+                value,
+                span,
+            } => {
+                let hlir_target = self.lower_expression(target);
+                let hlir_value = self.lower_expression(value);
+
+                if !Self::is_place(&hlir_target) {
+                    self.diagnostics.push(
+                        Diagnostic::error("invalid left-hand side of assignment")
+                            .with_code(codes::INVALID_ASSIGNMENT)
+                            .with_primary(hlir_target.span(), "cannot assign to this expression"),
+                    );
+                }
+
+                match op {
+                    Some(BinaryOperator::Shl | BinaryOperator::Shr) => {
+                        self.constraints
+                            .push(Constraint::Integral(hlir_target.ty(), *span));
+                        self.constraints
+                            .push(Constraint::Integral(hlir_value.ty(), hlir_value.span()));
+                    }
+                    Some(BinaryOperator::Add | BinaryOperator::Sub) => {
+                        self.constraints.push(Constraint::Additive(
+                            hlir_target.ty(),
+                            hlir_value.ty(),
+                            hlir_target.ty(),
+                            *span,
+                        ));
+                    }
+                    Some(op) => {
+                        self.constraints.push(Constraint::Equal(
+                            hlir_target.ty(),
+                            hlir_value.ty(),
+                            hlir_value.span(),
+                        ));
+                        self.constraints.push(match op {
+                            BinaryOperator::BitAnd
+                            | BinaryOperator::BitOr
+                            | BinaryOperator::BitXor => {
+                                Constraint::Integral(hlir_target.ty(), *span)
+                            }
+                            BinaryOperator::WrappingAdd
+                            | BinaryOperator::WrappingSub
+                            | BinaryOperator::WrappingMul
+                            | BinaryOperator::SaturatingAdd
+                            | BinaryOperator::SaturatingSub
+                            | BinaryOperator::SaturatingMul => Constraint::IntegerArithmetic(
+                                hlir_target.ty(),
+                                Self::operator_symbol(*op),
+                                *span,
+                            ),
+                            _ => Constraint::Numeric(hlir_target.ty(), *span),
+                        });
+                    }
+                    None => self.constraints.push(Constraint::Equal(
+                        hlir_target.ty(),
+                        hlir_value.ty(),
+                        hlir_value.span(),
+                    )),
+                }
+
+                HLIRStatement::Assignment {
+                    target: hlir_target,
+                    op: op.map(|op| self.lower_binary_operator(op)),
+                    value: hlir_value,
+                    span: *span,
+                }
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+                span,
+            } => {
+                let hlir_condition = self.lower_expression(condition);
+                self.constraints
+                    .push(Constraint::Bool(hlir_condition.ty(), hlir_condition.span()));
+
+                let then_branch = self.lower_block(then_branch, return_type);
+                let else_branch = else_branch
+                    .as_ref()
+                    .map(|else_branch| self.lower_block(else_branch, return_type));
+
+                HLIRStatement::If {
+                    condition: hlir_condition,
+                    then_branch,
+                    else_branch,
+                    span: *span,
+                }
+            }
+            Statement::While {
+                condition,
+                body,
+                span,
+            } => {
+                let hlir_condition = self.lower_expression(condition);
+                self.constraints
+                    .push(Constraint::Bool(hlir_condition.ty(), hlir_condition.span()));
+
+                self.loops.push(*span);
+                let body = self.lower_block(body, return_type);
+                self.loops.pop();
+
+                HLIRStatement::While {
+                    condition: hlir_condition,
+                    body,
+                    span: *span,
+                }
+            }
+            Statement::Break(span) => {
+                self.check_in_loop("break", *span);
+                HLIRStatement::Break(*span)
+            }
+            Statement::Continue(span) => {
+                self.check_in_loop("continue", *span);
+                HLIRStatement::Continue(*span)
+            }
         }
     }
 
-    fn lower_expression(
-        &mut self,
-        expression: &Expression<'source>,
-        named_values: &HashMap<&'source str, HLIRType>,
-    ) -> HLIRExpression<'source> {
+    /// Whether the expression refers to a location that can be assigned to
+    /// and have its address taken.
+    fn is_place(expression: &HLIRExpression<'source>) -> bool {
+        matches!(
+            expression,
+            HLIRExpression::Variable(..) | HLIRExpression::Dereference { .. }
+        )
+    }
+
+    fn check_in_loop(&mut self, keyword: &str, span: Span) {
+        if self.loops.is_empty() {
+            self.diagnostics.push(
+                Diagnostic::error(format!("`{}` outside of a loop", keyword))
+                    .with_code(codes::OUTSIDE_OF_LOOP)
+                    .with_primary(span, format!("cannot `{}` outside of a loop", keyword)),
+            );
+        }
+    }
+
+    fn lower_expression(&mut self, expression: &Expression<'source>) -> HLIRExpression<'source> {
         match expression {
-            Expression::Literal(literal) => self.lower_literal(literal),
-            Expression::Variable(name) => {
-                let ty = named_values.get(name).unwrap();
-                HLIRExpression::Variable(name, *ty)
+            Expression::Literal(literal, span) => self.lower_literal(literal, *span),
+            Expression::Variable(name, span) => {
+                if let Some((binding, ty)) = self.lookup_variable(name) {
+                    return HLIRExpression::Variable(name, binding, ty, *span);
+                }
+                if let Some((ty, _)) = self.hlir_program.globals.get(name) {
+                    return HLIRExpression::Constant(name, ty.clone(), *span);
+                }
+
+                // A constant that could not be evaluated has already been
+                // reported.
+                if !self.constant_spans.contains_key(name) {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!("cannot find value `{}` in this scope", name))
+                            .with_code(codes::UNRESOLVED_NAME)
+                            .with_primary(*span, "not found in this scope"),
+                    );
+                }
+                let ty = self.fresh_type_variable();
+                if let HLIRType::Var(id) = ty {
+                    self.unresolved_variables.push(id);
+                }
+                let binding = self.fresh_binding_id();
+                HLIRExpression::Variable(name, binding, ty, *span)
             }
             Expression::BinaryOperation {
                 ref lhs,
                 op,
                 ref rhs,
+                span,
             } => {
-                let hlir_lhs = Box::new(self.lower_expression(lhs, named_values));
-                let hlir_rhs = Box::new(self.lower_expression(rhs, named_values));
-                let ty = self.lower_binary_operator_res_ty(*op, &hlir_lhs.ty(), &hlir_rhs.ty());
+                let hlir_lhs = Box::new(self.lower_expression(lhs));
+                let hlir_rhs = Box::new(self.lower_expression(rhs));
+                let ty = self.lower_binary_operator_res_ty(*op, &hlir_lhs, &hlir_rhs, *span);
                 HLIRExpression::BinaryOperation {
                     lhs: hlir_lhs,
                     op: self.lower_binary_operator(*op),
                     rhs: hlir_rhs,
                     ty,
+                    span: *span,
+                }
+            }
+            Expression::UnaryOperation { op, operand, span } => {
+                let hlir_operand = Box::new(self.lower_expression(operand));
+                let ty = match op {
+                    UnaryOperator::Neg => {
+                        self.constraints
+                            .push(Constraint::Signed(hlir_operand.ty(), *span));
+                        hlir_operand.ty()
+                    }
+                    UnaryOperator::Not => {
+                        self.constraints
+                            .push(Constraint::Bool(hlir_operand.ty(), hlir_operand.span()));
+                        HLIRType::Bool
+                    }
+                    UnaryOperator::BitNot => {
+                        self.constraints
+                            .push(Constraint::Integral(hlir_operand.ty(), *span));
+                        hlir_operand.ty()
+                    }
+                };
+                HLIRExpression::UnaryOperation {
+                    op: self.lower_unary_operator(*op),
+                    operand: hlir_operand,
+                    ty,
+                    span: *span,
+                }
+            }
+            Expression::Call {
+                callee,
+                arguments,
+                span,
+            } => {
+                if !self.hlir_program.prototypes.contains_key(callee) {
+                    if let Some(intrinsic) = self.lower_intrinsic(callee, arguments, *span) {
+                        return intrinsic;
+                    }
+                }
+
+                let hlir_arguments: Vec<_> = arguments
+                    .iter()
+                    .map(|argument| self.lower_expression(argument))
+                    .collect();
+                let ty = self.lower_call(callee, &hlir_arguments, *span);
+                HLIRExpression::Call {
+                    callee,
+                    arguments: hlir_arguments,
+                    ty,
+                    span: *span,
+                }
+            }
+            Expression::Cast { value, ty, span } => {
+                let hlir_value = Box::new(self.lower_expression(value));
+                let ty = self.lower_type(ty);
+                self.constraints
+                    .push(Constraint::Cast(hlir_value.ty(), ty.clone(), *span));
+                HLIRExpression::Cast {
+                    value: hlir_value,
+                    ty,
+                    span: *span,
+                }
+            }
+            Expression::AddressOf { operand, span } => {
+                let hlir_operand = Box::new(self.lower_expression(operand));
+                if !Self::is_place(&hlir_operand) {
+                    self.diagnostics.push(
+                        Diagnostic::error("cannot take the address of this expression")
+                            .with_code(codes::INVALID_ADDRESS_OF)
+                            .with_primary(hlir_operand.span(), "this value has no address")
+                            .with_help("store the value in a variable and take its address"),
+                    );
+                }
+                HLIRExpression::AddressOf {
+                    ty: HLIRType::pointer_to(hlir_operand.ty()),
+                    operand: hlir_operand,
+                    span: *span,
+                }
+            }
+            Expression::Dereference { operand, span } => {
+                let hlir_operand = Box::new(self.lower_expression(operand));
+                let ty = self.fresh_type_variable();
+                self.constraints.push(Constraint::Equal(
+                    HLIRType::pointer_to(ty.clone()),
+                    hlir_operand.ty(),
+                    hlir_operand.span(),
+                ));
+                self.constraints
+                    .push(Constraint::Pointee(ty.clone(), hlir_operand.span()));
+                HLIRExpression::Dereference {
+                    operand: hlir_operand,
+                    ty,
+                    span: *span,
+                }
+            }
+            // `p[i]` is lowered to `(p + i).*`.
+            Expression::Index { base, index, span } => {
+                let hlir_base = self.lower_expression(base);
+                let hlir_index = self.lower_expression(index);
+                let ty = self.fresh_type_variable();
+                self.constraints.push(Constraint::Equal(
+                    HLIRType::pointer_to(ty.clone()),
+                    hlir_base.ty(),
+                    hlir_base.span(),
+                ));
+                self.constraints
+                    .push(Constraint::Pointee(ty.clone(), hlir_base.span()));
+                self.constraints
+                    .push(Constraint::Integral(hlir_index.ty(), hlir_index.span()));
+                HLIRExpression::Dereference {
+                    operand: Box::new(HLIRExpression::BinaryOperation {
+                        ty: hlir_base.ty(),
+                        lhs: Box::new(hlir_base),
+                        op: HLIRBinaryOperator::Add,
+                        rhs: Box::new(hlir_index),
+                        span: *span,
+                    }),
+                    ty,
+                    span: *span,
+                }
+            }
+        }
+    }
+
+    /// Checks the arguments of a call against the callee's prototype,
+    /// returning the type of the call.
+    fn lower_call(
+        &mut self,
+        callee: &'source str,
+        arguments: &[HLIRExpression<'source>],
+        span: Span,
+    ) -> HLIRType {
+        let Some(prototype) = self.hlir_program.prototypes.get(callee).cloned() else {
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot find function `{}` in this scope", callee))
+                    .with_code(codes::UNRESOLVED_NAME)
+                    .with_primary(span, "not found in this scope"),
+            );
+            let ty = self.fresh_type_variable();
+            if let HLIRType::Var(id) = ty {
+                self.unresolved_variables.push(id);
+            }
+            return ty;
+        };
+
+        let parameters = &prototype.parameters;
+        let wrong_count = if prototype.variadic {
+            arguments.len() < parameters.len()
+        } else {
+            arguments.len() != parameters.len()
+        };
+        if wrong_count {
+            self.report_wrong_argument_count(
+                callee,
+                parameters.len(),
+                arguments.len(),
+                prototype.variadic,
+                span,
+            );
+        }
+
+        // Variadic arguments can have any type, which is inferred as usual.
+        for (parameter, argument) in parameters.iter().zip(arguments) {
+            self.constraints.push(Constraint::Equal(
+                parameter.clone(),
+                argument.ty(),
+                argument.span(),
+            ));
+        }
+
+        prototype.return_type
+    }
+
+    /// Reports a call with `found` arguments to a function taking
+    /// `expected`, or at least `expected` if it is `variadic`.
+    fn report_wrong_argument_count(
+        &mut self,
+        callee: &str,
+        expected: usize,
+        found: usize,
+        variadic: bool,
+        span: Span,
+    ) {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        let at_least = if variadic { "at least " } else { "" };
+        let mut diagnostic = Diagnostic::error(format!(
+            "this function takes {}{} argument{} but {} argument{} {} supplied",
+            at_least,
+            expected,
+            plural(expected),
+            found,
+            plural(found),
+            if found == 1 { "was" } else { "were" },
+        ))
+        .with_code(codes::WRONG_ARGUMENT_COUNT)
+        .with_primary(
+            span,
+            format!(
+                "expected {}{} argument{}",
+                at_least,
+                expected,
+                plural(expected)
+            ),
+        );
+        if let Some(definition) = self.function_spans.get(callee) {
+            diagnostic =
+                diagnostic.with_secondary(*definition, format!("`{}` defined here", callee));
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Lowers a call to a builtin memory operation, or returns `None` if
+    /// `callee` does not name one. The orderings of atomic intrinsics are
+    /// given as bare names, such as `atomic_load(p, acquire)`.
+    fn lower_intrinsic(
+        &mut self,
+        callee: &'source str,
+        arguments: &[Expression<'source>],
+        span: Span,
+    ) -> Option<HLIRExpression<'source>> {
+        // The number of value arguments, which are followed by the orderings.
+        let (value_count, ordering_count) = match callee {
+            "volatile_load" => (1, 0),
+            "volatile_store" => (2, 0),
+            "atomic_load" => (1, 1),
+            "atomic_store" | "atomic_fetch_add" => (2, 1),
+            "atomic_cmpxchg" => (3, 2),
+            _ => return None,
+        };
+
+        let ty = self.fresh_type_variable();
+        if arguments.len() != value_count + ordering_count {
+            self.report_wrong_argument_count(
+                callee,
+                value_count + ordering_count,
+                arguments.len(),
+                false,
+                span,
+            );
+            if let HLIRType::Var(id) = ty {
+                self.unresolved_variables.push(id);
+            }
+        }
+
+        let split = value_count.min(arguments.len());
+        let hlir_arguments: Vec<_> = arguments[..split]
+            .iter()
+            .map(|argument| self.lower_expression(argument))
+            .collect();
+        let orderings: Vec<_> = arguments[split..]
+            .iter()
+            .take(ordering_count)
+            .map(|argument| self.lower_atomic_ordering(argument))
+            .collect();
+        // Missing orderings have already been reported.
+        let ordering = |index: usize| {
+            orderings
+                .get(index)
+                .copied()
+                .unwrap_or((HLIRAtomicOrdering::SeqCst, span))
+        };
+
+        let intrinsic = match callee {
+            "volatile_load" => HLIRIntrinsic::VolatileLoad,
+            "volatile_store" => HLIRIntrinsic::VolatileStore,
+            "atomic_load" => {
+                let (ordering, ordering_span) = ordering(0);
+                self.check_atomic_ordering(ordering, ordering_span, true);
+                HLIRIntrinsic::AtomicLoad(ordering)
+            }
+            "atomic_store" => {
+                let (ordering, ordering_span) = ordering(0);
+                self.check_atomic_ordering(ordering, ordering_span, false);
+                HLIRIntrinsic::AtomicStore(ordering)
+            }
+            "atomic_cmpxchg" => {
+                // A failed exchange only loads.
+                let (failure, failure_span) = ordering(1);
+                self.check_atomic_ordering(failure, failure_span, true);
+                HLIRIntrinsic::AtomicCompareExchange {
+                    success: ordering(0).0,
+                    failure,
                 }
             }
+            _ => HLIRIntrinsic::AtomicFetchAdd(ordering(0).0),
+        };
+
+        // The first argument points to the value accessed, and every other
+        // argument has the type of that value.
+        let mut hlir_arguments = hlir_arguments.into_iter();
+        let pointer = hlir_arguments.next();
+        if let Some(pointer) = &pointer {
+            self.constraints.push(Constraint::Equal(
+                HLIRType::pointer_to(ty.clone()),
+                pointer.ty(),
+                pointer.span(),
+            ));
+            self.constraints
+                .push(Constraint::Pointee(ty.clone(), pointer.span()));
+        }
+        let values: Vec<_> = hlir_arguments.collect();
+        for value in &values {
+            self.constraints
+                .push(Constraint::Equal(ty.clone(), value.ty(), value.span()));
+        }
+        if matches!(
+            intrinsic,
+            HLIRIntrinsic::AtomicCompareExchange { .. } | HLIRIntrinsic::AtomicFetchAdd(_)
+        ) {
+            self.constraints
+                .push(Constraint::Atomic(ty.clone(), intrinsic.name(), span));
+        }
+
+        let result_ty = match intrinsic {
+            HLIRIntrinsic::VolatileStore | HLIRIntrinsic::AtomicStore(_) => HLIRType::Void,
+            _ => ty,
+        };
+        Some(HLIRExpression::Intrinsic {
+            intrinsic,
+            arguments: pointer.into_iter().chain(values).collect(),
+            ty: result_ty,
+            span,
+        })
+    }
+
+    fn lower_atomic_ordering(
+        &mut self,
+        argument: &Expression<'source>,
+    ) -> (HLIRAtomicOrdering, Span) {
+        let span = argument.span();
+        if let Expression::Variable(name, _) = argument {
+            if let Some(ordering) = HLIRAtomicOrdering::from_name(name) {
+                return (ordering, span);
+            }
+        }
+
+        self.diagnostics.push(
+            Diagnostic::error("expected a memory ordering")
+                .with_code(codes::INVALID_ATOMIC_ORDERING)
+                .with_primary(span, "not a memory ordering")
+                .with_help(
+                    "the orderings are `relaxed`, `acquire`, `release`, `acq_rel` and `seq_cst`",
+                ),
+        );
+        (HLIRAtomicOrdering::SeqCst, span)
+    }
+
+    /// Reports an ordering that an atomic load or store cannot have. Only
+    /// stores can release and only loads can acquire.
+    fn check_atomic_ordering(&mut self, ordering: HLIRAtomicOrdering, span: Span, load: bool) {
+        let valid = match ordering {
+            HLIRAtomicOrdering::Relaxed | HLIRAtomicOrdering::SeqCst => true,
+            HLIRAtomicOrdering::Acquire => load,
+            HLIRAtomicOrdering::Release => !load,
+            HLIRAtomicOrdering::AcqRel => false,
+        };
+        if valid {
+            return;
         }
+
+        let (access, help) = if load {
+            ("load", "loads can be `relaxed`, `acquire` or `seq_cst`")
+        } else {
+            ("store", "stores can be `relaxed`, `release` or `seq_cst`")
+        };
+        self.diagnostics.push(
+            Diagnostic::error(format!(
+                "an atomic {} cannot have ordering `{}`",
+                access, ordering
+            ))
+            .with_code(codes::INVALID_ATOMIC_ORDERING)
+            .with_primary(span, format!("invalid ordering for a {}", access))
+            .with_help(help),
+        );
     }
 
-    fn lower_literal(&mut self, literal: &Literal<'source>) -> HLIRExpression<'source> {
+    fn lower_literal(&mut self, literal: &Literal<'source>, span: Span) -> HLIRExpression<'source> {
         match literal {
-            Literal::Integer(value) => {
-                let id = self.next_variable_id;
-                self.next_variable_id += 1;
-                let ty = HLIRType::Var(id);
-                HLIRExpression::Literal(HLIRLiteral::Integer(value, ty), ty)
+            Literal::Integer(value, suffix) => {
+                let ty = match suffix {
+                    Some(suffix) => self.lower_type(suffix),
+                    None => {
+                        let ty = self.fresh_type_variable();
+                        self.constraints.push(Constraint::Integer(ty.clone(), span));
+                        ty
+                    }
+                };
+                HLIRExpression::Literal(HLIRLiteral::Integer(*value, ty.clone()), ty, span)
             }
             Literal::Float(value) => {
-                let id = self.next_variable_id;
-                self.next_variable_id += 1;
-                let ty = HLIRType::Var(id);
-                HLIRExpression::Literal(HLIRLiteral::Float(value, ty), ty)
+                let ty = self.fresh_type_variable();
+                self.constraints.push(Constraint::Float(ty.clone(), span));
+                HLIRExpression::Literal(HLIRLiteral::Float(value, ty.clone()), ty, span)
+            }
+            Literal::Bool(value) => {
+                HLIRExpression::Literal(HLIRLiteral::Bool(*value), HLIRType::Bool, span)
+            }
+            Literal::Null => {
+                let pointee = self.fresh_type_variable();
+                HLIRExpression::Literal(HLIRLiteral::Null, HLIRType::pointer_to(pointee), span)
             }
         }
     }
@@ -287,23 +2006,120 @@ impl<'source> ZoidLoweringContext<'source> {
             BinaryOperator::Mul => HLIRBinaryOperator::Mul,
             BinaryOperator::Div => HLIRBinaryOperator::Div,
             BinaryOperator::Rem => HLIRBinaryOperator::Rem,
+            BinaryOperator::WrappingAdd => HLIRBinaryOperator::WrappingAdd,
+            BinaryOperator::WrappingSub => HLIRBinaryOperator::WrappingSub,
+            BinaryOperator::WrappingMul => HLIRBinaryOperator::WrappingMul,
+            BinaryOperator::SaturatingAdd => HLIRBinaryOperator::SaturatingAdd,
+            BinaryOperator::SaturatingSub => HLIRBinaryOperator::SaturatingSub,
+            BinaryOperator::SaturatingMul => HLIRBinaryOperator::SaturatingMul,
+            BinaryOperator::BitAnd => HLIRBinaryOperator::BitAnd,
+            BinaryOperator::BitOr => HLIRBinaryOperator::BitOr,
+            BinaryOperator::BitXor => HLIRBinaryOperator::BitXor,
+            BinaryOperator::Shl => HLIRBinaryOperator::Shl,
+            BinaryOperator::Shr => HLIRBinaryOperator::Shr,
+            BinaryOperator::Eq => HLIRBinaryOperator::Eq,
+            BinaryOperator::Ne => HLIRBinaryOperator::Ne,
+            BinaryOperator::Lt => HLIRBinaryOperator::Lt,
+            BinaryOperator::Le => HLIRBinaryOperator::Le,
+            BinaryOperator::Gt => HLIRBinaryOperator::Gt,
+            BinaryOperator::Ge => HLIRBinaryOperator::Ge,
+            BinaryOperator::And => HLIRBinaryOperator::And,
+            BinaryOperator::Or => HLIRBinaryOperator::Or,
+        }
+    }
+
+    /// The symbol of a wrapping or saturating operator, for diagnostics.
+    fn operator_symbol(op: BinaryOperator) -> &'static str {
+        match op {
+            BinaryOperator::WrappingAdd => "+%",
+            BinaryOperator::WrappingSub => "-%",
+            BinaryOperator::WrappingMul => "*%",
+            BinaryOperator::SaturatingAdd => "+|",
+            BinaryOperator::SaturatingSub => "-|",
+            BinaryOperator::SaturatingMul => "*|",
+            _ => unreachable!("{:?} is not a wrapping or saturating operator", op),
+        }
+    }
+
+    fn lower_unary_operator(&mut self, op: UnaryOperator) -> HLIRUnaryOperator {
+        match op {
+            UnaryOperator::Neg => HLIRUnaryOperator::Neg,
+            UnaryOperator::Not => HLIRUnaryOperator::Not,
+            UnaryOperator::BitNot => HLIRUnaryOperator::BitNot,
         }
     }
 
     fn lower_binary_operator_res_ty(
         &mut self,
-        _op: BinaryOperator,
-        lhs_ty: &HLIRType,
-        rhs_ty: &HLIRType,
+        op: BinaryOperator,
+        lhs: &HLIRExpression<'source>,
+        rhs: &HLIRExpression<'source>,
+        span: Span,
     ) -> HLIRType {
-        let id = self.next_variable_id;
-        self.next_variable_id += 1;
-        let ty = HLIRType::Var(id);
-        self.constraints.push(Constraint::Equal(*lhs_ty, *rhs_ty));
-        self.constraints.push(Constraint::Equal(*lhs_ty, ty));
-        self.constraints.push(Constraint::Equal(*rhs_ty, ty));
+        match op {
+            BinaryOperator::And | BinaryOperator::Or => {
+                self.constraints
+                    .push(Constraint::Bool(lhs.ty(), lhs.span()));
+                self.constraints
+                    .push(Constraint::Bool(rhs.ty(), rhs.span()));
+                HLIRType::Bool
+            }
+            BinaryOperator::Eq
+            | BinaryOperator::Ne
+            | BinaryOperator::Lt
+            | BinaryOperator::Le
+            | BinaryOperator::Gt
+            | BinaryOperator::Ge => {
+                self.constraints
+                    .push(Constraint::Equal(lhs.ty(), rhs.ty(), span));
+                HLIRType::Bool
+            }
+            BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor => {
+                self.constraints
+                    .push(Constraint::Equal(lhs.ty(), rhs.ty(), span));
+                self.constraints.push(Constraint::Integral(lhs.ty(), span));
+                lhs.ty()
+            }
+            BinaryOperator::WrappingAdd
+            | BinaryOperator::WrappingSub
+            | BinaryOperator::WrappingMul
+            | BinaryOperator::SaturatingAdd
+            | BinaryOperator::SaturatingSub
+            | BinaryOperator::SaturatingMul => {
+                self.constraints
+                    .push(Constraint::Equal(lhs.ty(), rhs.ty(), span));
+                self.constraints.push(Constraint::IntegerArithmetic(
+                    lhs.ty(),
+                    Self::operator_symbol(op),
+                    span,
+                ));
+                lhs.ty()
+            }
+            // The amount to shift by can have any integer type.
+            BinaryOperator::Shl | BinaryOperator::Shr => {
+                self.constraints
+                    .push(Constraint::Integral(lhs.ty(), lhs.span()));
+                self.constraints
+                    .push(Constraint::Integral(rhs.ty(), rhs.span()));
+                lhs.ty()
+            }
+            BinaryOperator::Add | BinaryOperator::Sub => {
+                let ty = self.fresh_type_variable();
+                self.constraints
+                    .push(Constraint::Additive(lhs.ty(), rhs.ty(), ty.clone(), span));
+                ty
+            }
+            _ => {
+                let ty = self.fresh_type_variable();
+                self.constraints
+                    .push(Constraint::Equal(lhs.ty(), rhs.ty(), span));
+                self.constraints
+                    .push(Constraint::Equal(lhs.ty(), ty.clone(), span));
+                self.constraints.push(Constraint::Numeric(ty.clone(), span));
 
-        ty
+                ty
+            }
+        }
     }
 
     fn propagate_types(&mut self) -> HLIRProgram<'source> {
@@ -326,81 +2142,327 @@ impl<'source> ZoidLoweringContext<'source> {
         &mut self,
         function: &mut HLIRFunction<'source>,
     ) -> HLIRFunction<'source> {
-        let mut named_values = HashMap::new();
-        for parameter in function.parameters.iter() {
-            named_values.insert(parameter.name, parameter.ty);
-        }
-
-        let mut new_body = Vec::new();
-        for statement in function.body.iter_mut() {
-            new_body.push(self.propagate_types_in_statement(statement, &mut named_values));
-        }
+        let new_body = self.propagate_types_in_block(&mut function.body);
 
         HLIRFunction {
             name: function.name,
             parameters: function.parameters.clone(),
-            return_type: function.return_type,
+            return_type: function.return_type.clone(),
             body: new_body,
+            span: function.span,
         }
     }
 
+    fn propagate_types_in_block(
+        &mut self,
+        block: &mut [HLIRStatement<'source>],
+    ) -> Vec<HLIRStatement<'source>> {
+        block
+            .iter_mut()
+            .map(|statement| self.propagate_types_in_statement(statement))
+            .collect()
+    }
+
     fn propagate_types_in_statement(
         &mut self,
         statement: &mut HLIRStatement<'source>,
-        named_values: &mut HashMap<&'source str, HLIRType>,
     ) -> HLIRStatement<'source> {
         match statement {
-            HLIRStatement::VariableDeclaration { name, ty, value } => {
+            HLIRStatement::VariableDeclaration {
+                name,
+                binding,
+                ty,
+                value,
+                span,
+            } => {
                 *ty = self.apply_substitution(ty);
-                named_values.insert(name, *ty);
+
+                if *ty == HLIRType::Void {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!("variable `{}` cannot have type `void`", name))
+                            .with_code(codes::MISMATCHED_TYPES)
+                            .with_primary(value.span(), "this expression has type `void`"),
+                    );
+                }
+
+                if self.needs_annotation(ty) {
+                    self.diagnostics.push(
+                        Diagnostic::error("type annotations needed")
+                            .with_code(codes::TYPE_ANNOTATIONS_NEEDED)
+                            .with_primary(*span, format!("cannot infer the type of `{}`", name))
+                            .with_help(format!(
+                                "give `{}` an explicit type, such as `let {}: i32`",
+                                name, name
+                            )),
+                    );
+                    self.mark_reported(ty);
+                }
 
                 HLIRStatement::VariableDeclaration {
                     name,
-                    ty: *ty,
-                    value: self.propagate_types_in_expression(value, named_values),
+                    binding: *binding,
+                    ty: ty.clone(),
+                    value: self.propagate_types_in_expression(value),
+                    span: *span,
                 }
             }
-            HLIRStatement::Return(Some(value)) => HLIRStatement::Return(Some(
-                self.propagate_types_in_expression(value, named_values),
-            )),
-            HLIRStatement::Return(None) => HLIRStatement::Return(None),
+            HLIRStatement::Return(Some(value), span) => {
+                // Only a call can have type `void`, and only in a `void`
+                // function does that pass type checking.
+                if self.apply_substitution(&value.ty()) == HLIRType::Void {
+                    self.diagnostics.push(
+                        Diagnostic::error("cannot return a value of type `void`")
+                            .with_code(codes::MISMATCHED_TYPES)
+                            .with_primary(value.span(), "this expression has type `void`")
+                            .with_help("call it on its own, then use `return;`"),
+                    );
+                }
+                HLIRStatement::Return(Some(self.propagate_types_in_expression(value)), *span)
+            }
+            HLIRStatement::Return(None, span) => HLIRStatement::Return(None, *span),
+            HLIRStatement::Expression(expression, span) => {
+                HLIRStatement::Expression(self.propagate_types_in_expression(expression), *span)
+            }
+            HLIRStatement::Assignment {
+                target,
+                op,
+                value,
+                span,
+            } => HLIRStatement::Assignment {
+                target: self.propagate_types_in_expression(target),
+                op: *op,
+                value: self.propagate_types_in_expression(value),
+                span: *span,
+            },
+            HLIRStatement::If {
+                condition,
+                then_branch,
+                else_branch,
+                span,
+            } => HLIRStatement::If {
+                condition: self.propagate_types_in_expression(condition),
+                then_branch: self.propagate_types_in_block(then_branch),
+                else_branch: else_branch
+                    .as_mut()
+                    .map(|else_branch| self.propagate_types_in_block(else_branch)),
+                span: *span,
+            },
+            HLIRStatement::While {
+                condition,
+                body,
+                span,
+            } => HLIRStatement::While {
+                condition: self.propagate_types_in_expression(condition),
+                body: self.propagate_types_in_block(body),
+                span: *span,
+            },
+            HLIRStatement::Break(span) => HLIRStatement::Break(*span),
+            HLIRStatement::Continue(span) => HLIRStatement::Continue(*span),
         }
     }
 
+    /// Propagates types into an expression, reporting it if its type could
+    /// not be inferred and is not part of one that already has been.
     fn propagate_types_in_expression(
         &mut self,
         expression: &mut HLIRExpression<'source>,
-        named_values: &mut HashMap<&'source str, HLIRType>,
     ) -> HLIRExpression<'source> {
-        match expression {
-            HLIRExpression::Variable(name, ty) => {
-                if let Some(t) = named_values.get(name) {
-                    *ty = *t;
-                } else {
-                    *ty = self.apply_substitution(ty);
-                }
+        let expression = self.propagate_types_in_expression_kind(expression);
+        let ty = self.apply_substitution(&expression.ty());
+        if self.needs_annotation(&ty) {
+            self.diagnostics.push(
+                Diagnostic::error("type annotations needed")
+                    .with_code(codes::TYPE_ANNOTATIONS_NEEDED)
+                    .with_primary(
+                        expression.span(),
+                        "cannot infer the type of this expression",
+                    ),
+            );
+            self.mark_reported(&ty);
+        }
+        expression
+    }
 
-                HLIRExpression::Variable(name, *ty)
+    fn propagate_types_in_expression_kind(
+        &mut self,
+        expression: &mut HLIRExpression<'source>,
+    ) -> HLIRExpression<'source> {
+        match expression {
+            HLIRExpression::Variable(name, binding, ty, span) => {
+                HLIRExpression::Variable(name, *binding, self.apply_substitution(ty), *span)
+            }
+            HLIRExpression::Constant(name, ty, span) => {
+                HLIRExpression::Constant(name, ty.clone(), *span)
             }
-            HLIRExpression::BinaryOperation { lhs, rhs, ty, op } => {
-                let lhs = self.propagate_types_in_expression(lhs, named_values);
-                let rhs = self.propagate_types_in_expression(rhs, named_values);
+            HLIRExpression::BinaryOperation {
+                lhs,
+                rhs,
+                ty,
+                op,
+                span,
+            } => {
+                let lhs = self.propagate_types_in_expression(lhs);
+                let rhs = self.propagate_types_in_expression(rhs);
                 *ty = self.apply_substitution(ty);
 
                 HLIRExpression::BinaryOperation {
                     lhs: Box::new(lhs),
-                    op: op.clone(),
+                    op: *op,
                     rhs: Box::new(rhs),
-                    ty: *ty,
+                    ty: ty.clone(),
+                    span: *span,
                 }
             }
-            HLIRExpression::Literal(literal, ty) => {
+            HLIRExpression::UnaryOperation {
+                op,
+                operand,
+                ty,
+                span,
+            } => {
+                // A negated integer literal is checked as a whole, so that
+                // `-128i8` is in range, and folded into a negative literal,
+                // which negating at run time would report as an overflow.
+                let operand = match (&op, operand.as_mut()) {
+                    (
+                        HLIRUnaryOperator::Neg,
+                        HLIRExpression::Literal(literal, literal_ty, literal_span),
+                    ) => match self.propagate_types_in_literal_expression(
+                        literal,
+                        literal_ty,
+                        *literal_span,
+                        true,
+                    ) {
+                        HLIRExpression::Literal(HLIRLiteral::Integer(value, ty), literal_ty, _) => {
+                            return HLIRExpression::Literal(
+                                HLIRLiteral::Integer(value.wrapping_neg(), ty),
+                                literal_ty,
+                                *span,
+                            );
+                        }
+                        operand => operand,
+                    },
+                    (_, operand) => self.propagate_types_in_expression(operand),
+                };
+                *ty = self.apply_substitution(ty);
+
+                HLIRExpression::UnaryOperation {
+                    op: *op,
+                    operand: Box::new(operand),
+                    ty: ty.clone(),
+                    span: *span,
+                }
+            }
+            HLIRExpression::Call {
+                callee,
+                arguments,
+                ty,
+                span,
+            } => {
+                let arguments = arguments
+                    .iter_mut()
+                    .map(|argument| self.propagate_types_in_expression(argument))
+                    .collect();
                 *ty = self.apply_substitution(ty);
-                HLIRExpression::Literal(self.propagate_types_in_literal(literal), *ty)
+
+                HLIRExpression::Call {
+                    callee,
+                    arguments,
+                    ty: ty.clone(),
+                    span: *span,
+                }
+            }
+            HLIRExpression::Intrinsic {
+                intrinsic,
+                arguments,
+                ty,
+                span,
+            } => {
+                let arguments = arguments
+                    .iter_mut()
+                    .map(|argument| self.propagate_types_in_expression(argument))
+                    .collect();
+                *ty = self.apply_substitution(ty);
+
+                HLIRExpression::Intrinsic {
+                    intrinsic: *intrinsic,
+                    arguments,
+                    ty: ty.clone(),
+                    span: *span,
+                }
+            }
+            HLIRExpression::Cast { value, ty, span } => HLIRExpression::Cast {
+                value: Box::new(self.propagate_types_in_expression(value)),
+                ty: ty.clone(),
+                span: *span,
+            },
+            HLIRExpression::AddressOf { operand, ty, span } => HLIRExpression::AddressOf {
+                operand: Box::new(self.propagate_types_in_expression(operand)),
+                ty: self.apply_substitution(ty),
+                span: *span,
+            },
+            HLIRExpression::Dereference { operand, ty, span } => {
+                let operand = self.propagate_types_in_expression(operand);
+                let ty = self.apply_substitution(ty);
+                if self.needs_annotation(&ty) {
+                    self.diagnostics.push(
+                        Diagnostic::error("type annotations needed")
+                            .with_code(codes::TYPE_ANNOTATIONS_NEEDED)
+                            .with_primary(*span, "cannot infer the type of the value pointed to"),
+                    );
+                    self.mark_reported(&ty);
+                }
+                HLIRExpression::Dereference {
+                    operand: Box::new(operand),
+                    ty,
+                    span: *span,
+                }
+            }
+            HLIRExpression::Literal(literal, ty, span) => {
+                self.propagate_types_in_literal_expression(literal, ty, *span, false)
             }
         }
     }
 
+    /// Whether a type still contains a type variable that has not been
+    /// reported. Type variables of names that could not be resolved have
+    /// already been.
+    fn needs_annotation(&self, ty: &HLIRType) -> bool {
+        match ty {
+            HLIRType::Var(id) => !self.unresolved_variables.iter().any(|unresolved| {
+                self.apply_substitution(&HLIRType::Var(*unresolved)) == HLIRType::Var(*id)
+            }),
+            HLIRType::Pointer(pointee) => self.needs_annotation(pointee),
+            _ => false,
+        }
+    }
+
+    /// Records the type variables in `ty` as reported, so that expressions
+    /// sharing them are not reported as well.
+    fn mark_reported(&mut self, ty: &HLIRType) {
+        match self.apply_substitution(ty) {
+            HLIRType::Var(id) => self.unresolved_variables.push(id),
+            HLIRType::Pointer(pointee) => self.mark_reported(&pointee),
+            _ => (),
+        }
+    }
+
+    /// Propagates types into a literal, checking that an integer literal
+    /// fits in its type. `negative` is set for the operand of `-`.
+    fn propagate_types_in_literal_expression(
+        &mut self,
+        literal: &HLIRLiteral<'source>,
+        ty: &HLIRType,
+        span: Span,
+        negative: bool,
+    ) -> HLIRExpression<'source> {
+        let ty = self.apply_substitution(ty);
+        let literal = self.propagate_types_in_literal(literal);
+        if let HLIRLiteral::Integer(value, ty) = &literal {
+            self.check_integer_literal(*value, ty, negative, span);
+        }
+        HLIRExpression::Literal(literal, ty, span)
+    }
+
     fn propagate_types_in_literal(
         &mut self,
         literal: &HLIRLiteral<'source>,
@@ -409,9 +2471,187 @@ impl<'source> ZoidLoweringContext<'source> {
             HLIRLiteral::Integer(value, ty) => {
                 HLIRLiteral::Integer(*value, self.apply_substitution(ty))
             }
-            HLIRLiteral::Float(value, ty) => {
-                HLIRLiteral::Float(*value, self.apply_substitution(ty))
-            }
+            HLIRLiteral::Float(value, ty) => HLIRLiteral::Float(value, self.apply_substitution(ty)),
+            HLIRLiteral::Bool(value) => HLIRLiteral::Bool(*value),
+            HLIRLiteral::Null => HLIRLiteral::Null,
+        }
+    }
+
+    /// Checks that an integer literal fits in its inferred type, as LLVM
+    /// would otherwise silently truncate it, returning whether it fits. A
+    /// `negative` literal of a signed type may be one more than the type's
+    /// maximum.
+    fn check_integer_literal(
+        &mut self,
+        value: u128,
+        ty: &HLIRType,
+        negative: bool,
+        span: Span,
+    ) -> bool {
+        let Some(max) = ty.integer_max() else {
+            return true;
+        };
+        if value <= max || (negative && ty.is_signed_integer() && value - 1 <= max) {
+            return true;
         }
+
+        let range = if ty.is_signed_integer() {
+            format!("-{}..={}", max + 1, max)
+        } else {
+            format!("0..={}", max)
+        };
+        self.diagnostics.push(
+            Diagnostic::error(format!("literal out of range for `{}`", ty))
+                .with_code(codes::LITERAL_OUT_OF_RANGE)
+                .with_primary(span, format!("does not fit in `{}`", ty))
+                .with_note(format!(
+                    "the literal `{}{}` does not fit in the type `{}` whose range is `{}`",
+                    if negative { "-" } else { "" },
+                    value,
+                    ty,
+                    range
+                )),
+        );
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zoid_diagnostics::ErrorCode;
+    use zoid_parser::Parser;
+    use zoid_span::FileId;
+
+    use super::*;
+
+    /// Lowers `source`, which must parse, and returns the codes of the
+    /// errors reported and the source text they point at.
+    fn errors(source: &str) -> Vec<(ErrorCode, &str)> {
+        let (program, diagnostics) = Parser::new(FileId(0), "test.zd", source).parse();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let mut lowering = ZoidLoweringContext::new(program);
+        lowering.lower();
+        lowering
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let span = diagnostic.primary_span().unwrap();
+                (diagnostic.code.unwrap(), &source[span.start..span.end])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn integer_literals_at_the_limits_of_their_type() {
+        assert_eq!(
+            errors(
+                "fn main() {
+                    let a: i8 = -128;
+                    let b: i8 = 127;
+                    let c: u8 = 255;
+                    let d: i128 = -170141183460469231731687303715884105728;
+                    let e: u128 = 340282366920938463463374607431768211455;
+                    let f = -128i8;
+                }"
+            ),
+            []
+        );
+    }
+
+    #[test]
+    fn integer_literals_out_of_range() {
+        assert_eq!(
+            errors(
+                "fn main() {
+                    let a: i8 = 128;
+                    let b: i8 = -129;
+                    let c: u8 = 256;
+                    let d: u8 = -1;
+                    let e = 128i8;
+                    let f: i128 = 170141183460469231731687303715884105728;
+                }"
+            ),
+            [
+                (codes::LITERAL_OUT_OF_RANGE, "128"),
+                (codes::LITERAL_OUT_OF_RANGE, "129"),
+                (codes::LITERAL_OUT_OF_RANGE, "256"),
+                // Unsigned values cannot be negated at all.
+                (codes::MISMATCHED_TYPES, "-1"),
+                (codes::LITERAL_OUT_OF_RANGE, "128i8"),
+                (
+                    codes::LITERAL_OUT_OF_RANGE,
+                    "170141183460469231731687303715884105728"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn constants_evaluated_in_any_order() {
+        assert_eq!(
+            errors(
+                "const LIMIT: i32 = BASE * 4 + 2;
+                const BASE: i32 = 10;
+                const NEG: i64 = 0 - 5;
+                const FLAG: bool = true and not false;
+                fn main(): i32 {
+                    let x = LIMIT;
+                    if FLAG {
+                        return x;
+                    }
+                    return 0;
+                }"
+            ),
+            []
+        );
+    }
+
+    #[test]
+    fn constant_cycles() {
+        assert_eq!(
+            errors(
+                "const A: i32 = B + 1;
+                const B: i32 = C * 2;
+                const C: i32 = A;
+                const D: i32 = D;"
+            ),
+            [
+                (codes::CONSTANT_CYCLE, "const A: i32 = B + 1;"),
+                (codes::CONSTANT_CYCLE, "const D: i32 = D;"),
+            ]
+        );
+    }
+
+    #[test]
+    fn constant_overflow() {
+        assert_eq!(
+            errors(
+                "const D: u8 = 200 + 100;
+                const E: i32 = 1 / 0;
+                const I: i8 = -128 - 1;"
+            ),
+            [
+                (codes::CONSTANT_EVALUATION, "200 + 100"),
+                (codes::CONSTANT_EVALUATION, "1 / 0"),
+                (codes::CONSTANT_EVALUATION, "-128 - 1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn return_of_void_call() {
+        assert_eq!(
+            errors(
+                "fn f() {}
+                fn g() {
+                    return f();
+                }
+                fn h() {
+                    f();
+                    return;
+                }"
+            ),
+            [(codes::MISMATCHED_TYPES, "f()")]
+        );
     }
 }
@@ -0,0 +1,129 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use zoid_diagnostics::{codes, Diagnostic};
+use zoid_lexer::token::TokenKind;
+use zoid_span::Span;
+
+/// A syntax error found while parsing. The parser records these and keeps
+/// going, so a single run can report every error in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// The kind of the offending token, or `None` if the end of the input was
+    /// reached.
+    pub found: Option<TokenKind>,
+    /// Where to report the error: the offending token, or the end of the last
+    /// token at the end of the input.
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A specific token (or one of a set of tokens) was required.
+    Expected(Vec<TokenKind>),
+    ExpectedType,
+    ExpectedExpression,
+    ExpectedStatement,
+    ExpectedTopLevel,
+    /// A digit that is not valid for the literal's radix, such as the `2` in
+    /// `0b102`.
+    InvalidDigit {
+        digit: char,
+        radix: u32,
+    },
+    /// A literal suffix that is not an integer type.
+    InvalidSuffix(String),
+    /// A prefixed literal without any digits, such as `0x`.
+    MissingDigits,
+    /// An integer literal that does not fit in 128 bits.
+    IntegerTooLarge,
+}
+
+impl ParseErrorKind {
+    /// Whether the error is about the contents of a literal token rather than
+    /// which token was found.
+    fn is_literal_error(&self) -> bool {
+        matches!(
+            self,
+            ParseErrorKind::InvalidDigit { .. }
+                | ParseErrorKind::InvalidSuffix(_)
+                | ParseErrorKind::MissingDigits
+                | ParseErrorKind::IntegerTooLarge
+        )
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ParseErrorKind::Expected(kinds) => match kinds.as_slice() {
+                [kind] => write!(f, "expected {}", kind),
+                kinds => {
+                    write!(f, "expected one of ")?;
+                    for (i, kind) in kinds.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", kind)?;
+                    }
+                    Ok(())
+                }
+            },
+            ParseErrorKind::ExpectedType => write!(f, "expected type"),
+            ParseErrorKind::ExpectedExpression => write!(f, "expected expression"),
+            ParseErrorKind::ExpectedStatement => write!(f, "expected statement"),
+            ParseErrorKind::ExpectedTopLevel => {
+                write!(f, "expected `fn`, `extern`, `cimport` or `const`")
+            }
+            ParseErrorKind::InvalidDigit { digit, radix } => {
+                write!(f, "invalid digit `{}` in base {} literal", digit, radix)
+            }
+            ParseErrorKind::InvalidSuffix(suffix) => {
+                write!(f, "invalid suffix `{}` for integer literal", suffix)
+            }
+            ParseErrorKind::MissingDigits => write!(f, "no digits in integer literal"),
+            ParseErrorKind::IntegerTooLarge => write!(f, "integer literal is too large"),
+        }
+    }
+}
+
+impl ParseError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let code = match self.kind {
+            ParseErrorKind::Expected(_) => codes::EXPECTED_TOKEN,
+            ParseErrorKind::ExpectedType => codes::EXPECTED_TYPE,
+            ParseErrorKind::ExpectedExpression => codes::EXPECTED_EXPRESSION,
+            ParseErrorKind::ExpectedStatement => codes::EXPECTED_STATEMENT,
+            ParseErrorKind::ExpectedTopLevel => codes::EXPECTED_ITEM,
+            ParseErrorKind::InvalidDigit { .. }
+            | ParseErrorKind::InvalidSuffix(_)
+            | ParseErrorKind::MissingDigits
+            | ParseErrorKind::IntegerTooLarge => codes::INVALID_LITERAL,
+        };
+
+        if self.kind.is_literal_error() {
+            let diagnostic = Diagnostic::error(self.kind.to_string())
+                .with_code(code)
+                .with_primary(self.span, "");
+            return match self.kind {
+                ParseErrorKind::InvalidSuffix(_) => diagnostic.with_help(
+                    "the suffix must be an integer type: `i8`, `i16`, `i32`, `i64`, `i128`, \
+                     `u8`, `u16`, `u32`, `u64` or `u128`",
+                ),
+                ParseErrorKind::IntegerTooLarge => {
+                    diagnostic.with_note("integer literals must fit in 128 bits")
+                }
+                _ => diagnostic,
+            };
+        }
+
+        let message = match self.found {
+            Some(found) => format!("{}, found {}", self.kind, found),
+            None => format!("{}, found end of file", self.kind),
+        };
+
+        Diagnostic::error(message)
+            .with_code(code)
+            .with_primary(self.span, self.kind.to_string())
+    }
+}
@@ -1,248 +1,863 @@
-use core::panic;
-
 use zoid_ast::{
     BinaryOperator, Expression, Literal, Parameter, Program, Statement, TopLevelExpression, Type,
+    UnaryOperator,
 };
+use zoid_diagnostics::Diagnostic;
 use zoid_lexer::{
     token::{Token, TokenKind},
     Lexer,
 };
+use zoid_span::{FileId, Span};
+
+pub use error::{ParseError, ParseErrorKind};
+
+pub mod error;
+
+type ParseResult<T> = Result<T, ParseError>;
 
 pub struct Parser<'fname, 'source> {
-    #[allow(unused)]
-    file: &'fname str,
+    file_id: FileId,
     lexer: Lexer<'fname, 'source>,
+    /// The most recently consumed token, used to find where a node ends.
+    previous: Option<Token<'fname, 'source>>,
+    /// The next token, once peeked, or `Some(None)` at the end of the input.
+    lookahead: Option<Option<Token<'fname, 'source>>>,
     program: Program<'source>,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'fname, 'source> Parser<'fname, 'source> {
-    pub fn new(file: &'fname str, source: &'source str) -> Self {
+    pub fn new(file_id: FileId, file: &'fname str, source: &'source str) -> Self {
         Self {
-            file,
-            lexer: Lexer::new(file, source),
+            file_id,
+            lexer: Lexer::new(file_id, file, source),
+            previous: None,
+            lookahead: None,
             program: Program(Vec::new()),
+            diagnostics: Vec::new(),
         }
     }
 
     pub fn reset(&mut self) {
         self.lexer.reset();
+        self.previous = None;
+        self.lookahead = None;
+        self.program.0.clear();
+        self.diagnostics.clear();
     }
 
-    fn expect(&mut self, kind: TokenKind) -> Token<'fname, 'source> {
-        if let Some(tok) = self.lexer.next() {
-            if tok.kind != kind {
-                panic!("Expected {:?}, got {:?}", kind, tok.kind);
+    fn error(&self, kind: ParseErrorKind, found: Option<Token<'fname, 'source>>) -> ParseError {
+        let span = match (found, self.previous) {
+            // A missing token at the end of a line (usually a `;`) is easier
+            // to spot if reported where it should have been.
+            (Some(tok), Some(prev))
+                if matches!(kind, ParseErrorKind::Expected(_)) && tok.line > prev.line =>
+            {
+                Span::new(self.file_id, prev.end, prev.end)
             }
-            tok
-        } else {
-            panic!("Expected {:?}, got EOF", kind);
+            (Some(tok), _) => tok.span(),
+            (None, Some(prev)) => Span::new(self.file_id, prev.end, prev.end),
+            (None, None) => Span::new(self.file_id, 0, 0),
+        };
+
+        ParseError {
+            kind,
+            found: found.map(|tok| tok.kind),
+            span,
         }
     }
 
-    fn expect_one_of(&mut self, kinds: &[TokenKind]) -> Token<'fname, 'source> {
-        if let Some(tok) = self.lexer.next() {
-            if !kinds.contains(&tok.kind) {
-                panic!("Expected one of {:?}, got {:?}", kinds, tok.kind);
+    fn expect(&mut self, kind: TokenKind) -> ParseResult<Token<'fname, 'source>> {
+        self.expect_one_of(&[kind])
+    }
+
+    fn expect_one_of(&mut self, kinds: &[TokenKind]) -> ParseResult<Token<'fname, 'source>> {
+        match self.peek() {
+            Some(tok) if kinds.contains(&tok.kind) => {
+                self.bump();
+                Ok(tok)
             }
-            tok
-        } else {
-            panic!("Expected one of {:?}, got EOF", kinds);
+            found => Err(self.error(ParseErrorKind::Expected(kinds.to_vec()), found)),
+        }
+    }
+
+    /// Unknown tokens are skipped, as the lexer has already reported them.
+    fn peek(&mut self) -> Option<Token<'fname, 'source>> {
+        *self
+            .lookahead
+            .get_or_insert_with(|| self.lexer.find(|tok| tok.kind != TokenKind::Unknown))
+    }
+
+    fn bump(&mut self) -> Option<Token<'fname, 'source>> {
+        let tok = self.peek();
+        self.lookahead = None;
+        if tok.is_some() {
+            self.previous = tok;
+        }
+        tok
+    }
+
+    /// Returns a span from the start of `start` to the end of the most
+    /// recently consumed token.
+    fn span_from(&self, start: Span) -> Span {
+        match self.previous {
+            Some(tok) => start.to(tok.span()),
+            None => start,
         }
     }
 
     fn next_is(&mut self, kind: TokenKind) -> bool {
-        if let Some(tok) = self.lexer.clone().peekable().peek() {
+        if let Some(tok) = self.peek() {
             tok.kind == kind
         } else {
             false
         }
     }
 
-    #[allow(dead_code)]
     fn next_is_one_of(&mut self, kinds: &[TokenKind]) -> bool {
-        if let Some(tok) = self.lexer.clone().peekable().peek() {
+        if let Some(tok) = self.peek() {
             kinds.contains(&tok.kind)
         } else {
             false
         }
     }
 
-    pub fn parse(&mut self) -> Program<'source> {
-        while let Some(tok) = self.lexer.next() {
-            match tok.kind {
-                TokenKind::KeywordFn => self.parse_function(),
-                _ => panic!("Unexpected token: {:?}", tok),
+    /// Parses the whole input, returning the program along with every lexical
+    /// and syntax error encountered. Items containing errors are skipped (or
+    /// truncated) in the returned program, so it should only be lowered if
+    /// there are no errors.
+    pub fn parse(&mut self) -> (Program<'source>, Vec<Diagnostic>) {
+        while let Some(tok) = self.peek() {
+            let result = match tok.kind {
+                TokenKind::KeywordFn => {
+                    self.bump();
+                    self.parse_function(tok.span())
+                }
+                TokenKind::KeywordExtern => {
+                    self.bump();
+                    self.parse_extern_function(tok.span())
+                }
+                TokenKind::KeywordCImport => {
+                    self.bump();
+                    self.parse_cimport(tok.span())
+                }
+                TokenKind::KeywordConst => {
+                    self.bump();
+                    self.parse_constant(tok.span())
+                }
+                _ => {
+                    self.bump();
+                    Err(self.error(ParseErrorKind::ExpectedTopLevel, Some(tok)))
+                }
+            };
+
+            if let Err(err) = result {
+                self.report(err);
+                self.synchronize_top_level();
             }
         }
 
-        self.program.clone()
+        let mut diagnostics = self.lexer.take_diagnostics();
+        diagnostics.append(&mut self.diagnostics);
+        diagnostics.sort_by_key(|diagnostic| diagnostic.primary_span().map(|span| span.start));
+
+        (self.program.clone(), diagnostics)
     }
 
-    fn parse_type(&mut self) -> Type {
-        match self.lexer.by_ref().next() {
-            Some(tok) => match tok.kind {
-                TokenKind::TypeI8 => Type::I8,
-                TokenKind::TypeI16 => Type::I16,
-                TokenKind::TypeI32 => Type::I32,
-                TokenKind::TypeI64 => Type::I64,
-                TokenKind::TypeI128 => Type::I128,
-                TokenKind::TypeU8 => Type::U8,
-                TokenKind::TypeU16 => Type::U16,
-                TokenKind::TypeU32 => Type::U32,
-                TokenKind::TypeU64 => Type::U64,
-                TokenKind::TypeU128 => Type::U128,
-                TokenKind::TypeF32 => Type::F32,
-                TokenKind::TypeF64 => Type::F64,
-                TokenKind::TypeVoid => Type::Void,
-                _ => panic!("Expected type, got {:?}", tok),
-            },
-            None => panic!("Expected type, got EOF"),
+    fn report(&mut self, err: ParseError) {
+        self.diagnostics.push(err.to_diagnostic());
+    }
+
+    /// Skips tokens until something that can start a top level item.
+    fn synchronize_top_level(&mut self) {
+        while let Some(tok) = self.peek() {
+            if matches!(
+                tok.kind,
+                TokenKind::KeywordFn
+                    | TokenKind::KeywordExtern
+                    | TokenKind::KeywordCImport
+                    | TokenKind::KeywordConst
+            ) {
+                return;
+            }
+            self.bump();
         }
     }
 
-    fn parse_function(&mut self) {
+    /// Skips tokens until the end of the current statement. A `;` is consumed,
+    /// while a `}` or `fn` is left for the enclosing block or item to handle.
+    fn synchronize_statement(&mut self) {
+        while let Some(tok) = self.peek() {
+            match tok.kind {
+                TokenKind::Semicolon => {
+                    self.bump();
+                    return;
+                }
+                TokenKind::RBrace | TokenKind::KeywordFn => return,
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    fn parse_type(&mut self) -> ParseResult<Type> {
+        let tok = self.peek();
+        let ty = match tok.map(|tok| tok.kind) {
+            Some(TokenKind::OpMul) => {
+                self.bump();
+                return Ok(Type::Pointer(Box::new(self.parse_type()?)));
+            }
+            Some(TokenKind::TypeI8) => Type::I8,
+            Some(TokenKind::TypeI16) => Type::I16,
+            Some(TokenKind::TypeI32) => Type::I32,
+            Some(TokenKind::TypeI64) => Type::I64,
+            Some(TokenKind::TypeI128) => Type::I128,
+            Some(TokenKind::TypeU8) => Type::U8,
+            Some(TokenKind::TypeU16) => Type::U16,
+            Some(TokenKind::TypeU32) => Type::U32,
+            Some(TokenKind::TypeU64) => Type::U64,
+            Some(TokenKind::TypeU128) => Type::U128,
+            Some(TokenKind::TypeF32) => Type::F32,
+            Some(TokenKind::TypeF64) => Type::F64,
+            Some(TokenKind::TypeBool) => Type::Bool,
+            Some(TokenKind::TypeVoid) => Type::Void,
+            _ => return Err(self.error(ParseErrorKind::ExpectedType, tok)),
+        };
+        self.bump();
+
+        Ok(ty)
+    }
+
+    fn parse_function(&mut self, start: Span) -> ParseResult<()> {
         let name = self
-            .expect(TokenKind::Identifier)
+            .expect(TokenKind::Identifier)?
             .value
             .expect("Expected non-empty identifier");
 
-        self.expect(TokenKind::LParen);
-        let parameters = self.parse_parameters();
-        self.expect(TokenKind::RParen);
+        self.expect(TokenKind::LParen)?;
+        let parameters = self.parse_parameters()?;
+        self.expect(TokenKind::RParen)?;
+        let return_type = self.parse_return_type()?;
+
+        let body = self.parse_block()?;
+
+        self.program.0.push(TopLevelExpression::Function {
+            name,
+            parameters,
+            return_type,
+            body,
+            span: self.span_from(start),
+        });
+
+        Ok(())
+    }
+
+    /// Parses `"C" fn name(a: T, ...): R;` after the `extern`.
+    fn parse_extern_function(&mut self, start: Span) -> ParseResult<()> {
+        let abi = self
+            .expect(TokenKind::StringLiteral)?
+            .value
+            .expect("Expected string literal value");
+        self.expect(TokenKind::KeywordFn)?;
+        let name = self
+            .expect(TokenKind::Identifier)?
+            .value
+            .expect("Expected non-empty identifier");
 
-        let return_type = if self.next_is(TokenKind::Colon) {
-            self.expect(TokenKind::Colon);
-            Some(self.parse_type())
+        self.expect(TokenKind::LParen)?;
+        let parameters = self.parse_parameters()?;
+        let variadic = if self.next_is(TokenKind::Ellipsis) {
+            self.bump();
+            true
         } else {
-            None
+            false
         };
+        self.expect(TokenKind::RParen)?;
+        let return_type = self.parse_return_type()?;
+        self.expect(TokenKind::Semicolon)?;
 
-        self.expect(TokenKind::LBrace);
+        self.program.0.push(TopLevelExpression::ExternFunction {
+            abi,
+            name,
+            parameters,
+            return_type,
+            variadic,
+            span: self.span_from(start),
+        });
 
-        let mut body = Vec::new();
+        Ok(())
+    }
 
-        while !self.next_is(TokenKind::RBrace) {
-            body.push(self.parse_statement());
+    /// Parses the `: T` after a function's parameters, if there is one.
+    fn parse_return_type(&mut self) -> ParseResult<Option<Type>> {
+        if self.next_is(TokenKind::Colon) {
+            self.expect(TokenKind::Colon)?;
+            Ok(Some(self.parse_type()?))
+        } else {
+            Ok(None)
         }
+    }
 
-        self.expect(TokenKind::RBrace);
+    fn parse_cimport(&mut self, start: Span) -> ParseResult<()> {
+        let header = self
+            .expect(TokenKind::StringLiteral)?
+            .value
+            .expect("Expected string literal value");
+        self.expect(TokenKind::Semicolon)?;
 
-        self.program.0.push(TopLevelExpression::Function {
+        self.program.0.push(TopLevelExpression::CImport {
+            header,
+            span: self.span_from(start),
+        });
+
+        Ok(())
+    }
+
+    fn parse_constant(&mut self, start: Span) -> ParseResult<()> {
+        let name = self
+            .expect(TokenKind::Identifier)?
+            .value
+            .expect("Expected non-empty identifier");
+
+        self.expect(TokenKind::Colon)?;
+        let ty = self.parse_type()?;
+
+        self.expect(TokenKind::OpAssign)?;
+        let value = self.parse_expression()?;
+        self.expect(TokenKind::Semicolon)?;
+
+        self.program.0.push(TopLevelExpression::Constant {
             name,
-            parameters,
-            return_type,
-            body,
+            ty,
+            value,
+            span: self.span_from(start),
         });
+
+        Ok(())
     }
 
-    fn parse_parameters(&mut self) -> Vec<Parameter<'source>> {
+    fn parse_parameters(&mut self) -> ParseResult<Vec<Parameter<'source>>> {
         let mut parameters = Vec::new();
 
-        if self.next_is(TokenKind::RParen) {
-            return parameters;
+        if self.next_is_one_of(&[TokenKind::RParen, TokenKind::Ellipsis]) {
+            return Ok(parameters);
         }
 
         loop {
-            let name = self
-                .expect(TokenKind::Identifier)
-                .value
-                .expect("Expected non-empty identifier");
+            let tok = self.expect(TokenKind::Identifier)?;
+            let name = tok.value.expect("Expected non-empty identifier");
 
-            self.expect(TokenKind::Colon);
-            let ty = self.parse_type();
+            self.expect(TokenKind::Colon)?;
+            let ty = self.parse_type()?;
 
-            parameters.push(Parameter { name, ty });
+            parameters.push(Parameter {
+                name,
+                ty,
+                span: self.span_from(tok.span()),
+            });
 
             if self.next_is(TokenKind::RParen) {
                 break;
             }
 
-            self.expect(TokenKind::Comma);
+            self.expect_one_of(&[TokenKind::Comma, TokenKind::RParen])?;
+            // A `...` can only come last, which `parse_extern_function`
+            // checks.
+            if self.next_is(TokenKind::Ellipsis) {
+                break;
+            }
         }
 
-        parameters
+        Ok(parameters)
     }
 
-    fn parse_statement(&mut self) -> Statement<'source> {
-        let tok = self.expect_one_of(&[TokenKind::KeywordLet, TokenKind::KeywordReturn]);
+    /// Parses a `{ ... }` block. Errors in individual statements are recorded
+    /// and skipped so that the rest of the block is still checked.
+    fn parse_block(&mut self) -> ParseResult<Vec<Statement<'source>>> {
+        self.expect(TokenKind::LBrace)?;
+
+        let mut body = Vec::new();
+
+        while self.peek().is_some()
+            && !self.next_is(TokenKind::RBrace)
+            && !self.next_is(TokenKind::KeywordFn)
+        {
+            match self.parse_statement() {
+                Ok(statement) => body.push(statement),
+                Err(err) => {
+                    self.report(err);
+                    self.synchronize_statement();
+                }
+            }
+        }
+
+        self.expect(TokenKind::RBrace)?;
+
+        Ok(body)
+    }
+
+    fn parse_statement(&mut self) -> ParseResult<Statement<'source>> {
+        let tok = match self.peek() {
+            Some(tok) => tok,
+            None => return Err(self.error(ParseErrorKind::ExpectedStatement, None)),
+        };
 
         match tok.kind {
-            TokenKind::KeywordLet => self.parse_variable_declaration(),
-            TokenKind::KeywordReturn => self.parse_return(),
-            _ => panic!("Expected statement, got {:?}", tok),
+            TokenKind::KeywordLet => {
+                self.bump();
+                self.parse_variable_declaration(tok.span())
+            }
+            TokenKind::KeywordReturn => {
+                self.bump();
+                self.parse_return(tok.span())
+            }
+            TokenKind::KeywordIf => {
+                self.bump();
+                self.parse_if(tok.span())
+            }
+            TokenKind::KeywordWhile => {
+                self.bump();
+                self.parse_while(tok.span())
+            }
+            TokenKind::KeywordBreak => {
+                self.bump();
+                self.expect(TokenKind::Semicolon)?;
+                Ok(Statement::Break(self.span_from(tok.span())))
+            }
+            TokenKind::KeywordContinue => {
+                self.bump();
+                self.expect(TokenKind::Semicolon)?;
+                Ok(Statement::Continue(self.span_from(tok.span())))
+            }
+            kind if Self::starts_expression(kind) => self.parse_expression_statement(tok.span()),
+            _ => Err(self.error(ParseErrorKind::ExpectedStatement, Some(tok))),
         }
     }
 
-    fn parse_variable_declaration(&mut self) -> Statement<'source> {
+    fn parse_variable_declaration(&mut self, start: Span) -> ParseResult<Statement<'source>> {
         let name = self
-            .expect(TokenKind::Identifier)
+            .expect(TokenKind::Identifier)?
             .value
             .expect("Expected non-empty identifier");
 
         let ty = if self.next_is(TokenKind::Colon) {
-            self.expect(TokenKind::Colon);
-            Some(self.parse_type())
+            self.expect(TokenKind::Colon)?;
+            Some(self.parse_type()?)
         } else {
             None
         };
 
-        self.expect(TokenKind::OpAssign);
+        self.expect(TokenKind::OpAssign)?;
 
-        let value = self.parse_expression();
+        let value = self.parse_expression()?;
 
-        self.expect(TokenKind::Semicolon);
+        self.expect(TokenKind::Semicolon)?;
 
-        Statement::VariableDeclaration { name, ty, value }
+        Ok(Statement::VariableDeclaration {
+            name,
+            ty,
+            value,
+            span: self.span_from(start),
+        })
     }
 
-    fn parse_return(&mut self) -> Statement<'source> {
+    fn parse_return(&mut self, start: Span) -> ParseResult<Statement<'source>> {
         let value = if self.next_is(TokenKind::Semicolon) {
             None
         } else {
-            Some(self.parse_expression())
+            Some(self.parse_expression()?)
+        };
+
+        self.expect(TokenKind::Semicolon)?;
+
+        Ok(Statement::Return(value, self.span_from(start)))
+    }
+
+    fn parse_if(&mut self, start: Span) -> ParseResult<Statement<'source>> {
+        let condition = self.parse_expression()?;
+        let then_branch = self.parse_block()?;
+
+        let else_branch = if self.next_is(TokenKind::KeywordElse) {
+            self.bump();
+            match self.peek() {
+                Some(tok) if tok.kind == TokenKind::KeywordIf => {
+                    self.bump();
+                    Some(vec![self.parse_if(tok.span())?])
+                }
+                _ => Some(self.parse_block()?),
+            }
+        } else {
+            None
+        };
+
+        Ok(Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_while(&mut self, start: Span) -> ParseResult<Statement<'source>> {
+        let condition = self.parse_expression()?;
+        let body = self.parse_block()?;
+
+        Ok(Statement::While {
+            condition,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parses an expression statement, or an assignment if the expression is
+    /// followed by `=` or a compound assignment operator.
+    fn parse_expression_statement(&mut self, start: Span) -> ParseResult<Statement<'source>> {
+        let expr = self.parse_expression()?;
+
+        let op = match self.peek().map(|tok| tok.kind) {
+            Some(TokenKind::OpAssign) => Some(None),
+            Some(TokenKind::OpAddAssign) => Some(Some(BinaryOperator::Add)),
+            Some(TokenKind::OpSubAssign) => Some(Some(BinaryOperator::Sub)),
+            Some(TokenKind::OpMulAssign) => Some(Some(BinaryOperator::Mul)),
+            Some(TokenKind::OpDivAssign) => Some(Some(BinaryOperator::Div)),
+            Some(TokenKind::OpRemAssign) => Some(Some(BinaryOperator::Rem)),
+            Some(TokenKind::OpWrappingAddAssign) => Some(Some(BinaryOperator::WrappingAdd)),
+            Some(TokenKind::OpWrappingSubAssign) => Some(Some(BinaryOperator::WrappingSub)),
+            Some(TokenKind::OpWrappingMulAssign) => Some(Some(BinaryOperator::WrappingMul)),
+            Some(TokenKind::OpSaturatingAddAssign) => Some(Some(BinaryOperator::SaturatingAdd)),
+            Some(TokenKind::OpSaturatingSubAssign) => Some(Some(BinaryOperator::SaturatingSub)),
+            Some(TokenKind::OpSaturatingMulAssign) => Some(Some(BinaryOperator::SaturatingMul)),
+            Some(TokenKind::OpBitAndAssign) => Some(Some(BinaryOperator::BitAnd)),
+            Some(TokenKind::OpBitOrAssign) => Some(Some(BinaryOperator::BitOr)),
+            Some(TokenKind::OpBitXorAssign) => Some(Some(BinaryOperator::BitXor)),
+            Some(TokenKind::OpShlAssign) => Some(Some(BinaryOperator::Shl)),
+            Some(TokenKind::OpShrAssign) => Some(Some(BinaryOperator::Shr)),
+            _ => None,
         };
 
-        self.expect(TokenKind::Semicolon);
+        let Some(op) = op else {
+            self.expect(TokenKind::Semicolon)?;
+            return Ok(Statement::Expression(expr, self.span_from(start)));
+        };
+
+        self.bump();
+        let value = self.parse_expression()?;
+        self.expect(TokenKind::Semicolon)?;
+
+        Ok(Statement::Assignment {
+            target: expr,
+            op,
+            value,
+            span: self.span_from(start),
+        })
+    }
 
-        Statement::Return(value)
+    /// Whether a token of this kind can be the first token of an expression.
+    fn starts_expression(kind: TokenKind) -> bool {
+        matches!(
+            kind,
+            TokenKind::IntegerLiteral
+                | TokenKind::FloatLiteral
+                | TokenKind::KeywordTrue
+                | TokenKind::KeywordFalse
+                | TokenKind::KeywordNull
+                | TokenKind::Identifier
+                | TokenKind::LParen
+                | TokenKind::OpSub
+                | TokenKind::OpNot
+                | TokenKind::KeywordNot
+                | TokenKind::OpBitNot
+        )
     }
 
     /// This method is heavily inspired by the Kaleidoscope tutorial:
     /// https://www.llvm.org/docs/tutorial/MyFirstLanguageFrontend/LangImpl02.html
-    fn parse_expression(&mut self) -> Expression<'source> {
-        let lhs = self.parse_expression_primary();
+    fn parse_expression(&mut self) -> ParseResult<Expression<'source>> {
+        let lhs = self.parse_cast_expression()?;
 
         self.parse_binary_operation(lhs, 0)
     }
 
-    fn parse_expression_primary(&mut self) -> Expression<'source> {
-        let tok = self.lexer.next().expect("Expected expression, got EOF");
+    /// Parses an operand of a binary operator along with any `as` casts
+    /// after it. Casts bind more tightly than binary operators but less
+    /// tightly than unary ones, so `-x as u8 * 2` is `((-x) as u8) * 2`.
+    fn parse_cast_expression(&mut self) -> ParseResult<Expression<'source>> {
+        let mut expr = self.parse_expression_primary()?;
 
-        match tok.kind {
+        while self.next_is(TokenKind::KeywordAs) {
+            self.bump();
+            let ty = self.parse_type()?;
+            let span = self.span_from(expr.span());
+            expr = Expression::Cast {
+                value: Box::new(expr),
+                ty,
+                span,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_expression_primary(&mut self) -> ParseResult<Expression<'source>> {
+        let tok = match self.peek() {
+            Some(tok) => tok,
+            None => return Err(self.error(ParseErrorKind::ExpectedExpression, None)),
+        };
+
+        let expr = match tok.kind {
             TokenKind::IntegerLiteral => {
-                let value = tok.value.expect("Expected non-empty integer literal");
-                Expression::Literal(Literal::Integer(value))
+                self.bump();
+                Expression::Literal(self.parse_integer_literal(tok), tok.span())
+            }
+            TokenKind::FloatLiteral => {
+                self.bump();
+                let value = tok.value.expect("Expected non-empty float literal");
+                Expression::Literal(Literal::Float(value), tok.span())
+            }
+            TokenKind::KeywordTrue | TokenKind::KeywordFalse => {
+                self.bump();
+                let value = tok.kind == TokenKind::KeywordTrue;
+                Expression::Literal(Literal::Bool(value), tok.span())
+            }
+            TokenKind::KeywordNull => {
+                self.bump();
+                Expression::Literal(Literal::Null, tok.span())
+            }
+            TokenKind::Identifier => {
+                self.bump();
+                let name = tok
+                    .value
+                    .expect("Expected non-empty identifier for variable expression");
+                if self.next_is(TokenKind::LParen) {
+                    self.parse_call(name, tok.span())?
+                } else {
+                    Expression::Variable(name, tok.span())
+                }
+            }
+            TokenKind::LParen => {
+                self.bump();
+                self.parse_paren_expression()?
+            }
+            TokenKind::OpNot | TokenKind::KeywordNot => {
+                self.bump();
+                self.parse_unary_operation(UnaryOperator::Not, tok.span())?
+            }
+            TokenKind::OpSub => {
+                self.bump();
+                self.parse_unary_operation(UnaryOperator::Neg, tok.span())?
+            }
+            TokenKind::OpBitNot => {
+                self.bump();
+                self.parse_unary_operation(UnaryOperator::BitNot, tok.span())?
+            }
+            _ => return Err(self.error(ParseErrorKind::ExpectedExpression, Some(tok))),
+        };
+
+        self.parse_postfix_operations(expr)
+    }
+
+    /// Parses any `.&`, `.*` and `[index]` after an expression, which bind
+    /// more tightly than any other operator.
+    fn parse_postfix_operations(
+        &mut self,
+        mut expr: Expression<'source>,
+    ) -> ParseResult<Expression<'source>> {
+        loop {
+            if self.next_is(TokenKind::Dot) {
+                self.bump();
+                let tok = self.peek();
+                let operand = Box::new(expr);
+                expr = match tok.map(|tok| tok.kind) {
+                    Some(TokenKind::OpBitAnd) => {
+                        self.bump();
+                        Expression::AddressOf {
+                            span: self.span_from(operand.span()),
+                            operand,
+                        }
+                    }
+                    Some(TokenKind::OpMul) => {
+                        self.bump();
+                        Expression::Dereference {
+                            span: self.span_from(operand.span()),
+                            operand,
+                        }
+                    }
+                    _ => {
+                        return Err(self.error(
+                            ParseErrorKind::Expected(vec![TokenKind::OpBitAnd, TokenKind::OpMul]),
+                            tok,
+                        ))
+                    }
+                };
+            } else if self.next_is(TokenKind::LBracket) {
+                self.bump();
+                let index = self.parse_expression()?;
+                self.expect(TokenKind::RBracket)?;
+                expr = Expression::Index {
+                    span: self.span_from(expr.span()),
+                    base: Box::new(expr),
+                    index: Box::new(index),
+                };
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// Parses the text of an integer literal token, such as `0xFF_FFu16`.
+    /// Malformed literals are reported but still produce a literal, so that
+    /// the rest of the expression is checked as usual.
+    fn parse_integer_literal(&mut self, tok: Token<'fname, 'source>) -> Literal<'source> {
+        let text = tok.value.expect("Expected non-empty integer literal");
+        let radix = match text.get(..2) {
+            Some("0x") => 16,
+            Some("0o") => 8,
+            Some("0b") => 2,
+            _ => 10,
+        };
+        let prefix_len = if radix == 10 { 0 } else { 2 };
+        let body_start = tok.start + prefix_len;
+        let body = &text[prefix_len..];
+
+        // The digits run until the first character that can't be a digit in
+        // any radix up to the literal's own, so that `0b102` is reported as
+        // a bad digit rather than a bad suffix.
+        let digits_len = body
+            .find(|c: char| c != '_' && !c.is_digit(radix.max(10)))
+            .unwrap_or(body.len());
+        let (digits, suffix) = body.split_at(digits_len);
+
+        let suffix_ty = match suffix {
+            "" => None,
+            "i8" => Some(Type::I8),
+            "i16" => Some(Type::I16),
+            "i32" => Some(Type::I32),
+            "i64" => Some(Type::I64),
+            "i128" => Some(Type::I128),
+            "u8" => Some(Type::U8),
+            "u16" => Some(Type::U16),
+            "u32" => Some(Type::U32),
+            "u64" => Some(Type::U64),
+            "u128" => Some(Type::U128),
+            _ => {
+                let start = body_start + digits_len;
+                self.report(ParseError {
+                    kind: ParseErrorKind::InvalidSuffix(suffix.to_string()),
+                    found: Some(tok.kind),
+                    span: Span::new(self.file_id, start, tok.end),
+                });
+                None
+            }
+        };
+
+        if let Some((i, digit)) = digits
+            .char_indices()
+            .find(|(_, c)| *c != '_' && !c.is_digit(radix))
+        {
+            let start = body_start + i;
+            self.report(ParseError {
+                kind: ParseErrorKind::InvalidDigit { digit, radix },
+                found: Some(tok.kind),
+                span: Span::new(self.file_id, start, start + digit.len_utf8()),
+            });
+            return Literal::Integer(0, suffix_ty);
+        }
+
+        let digits: String = digits.chars().filter(|c| *c != '_').collect();
+        let kind = if digits.is_empty() {
+            ParseErrorKind::MissingDigits
+        } else {
+            match u128::from_str_radix(&digits, radix) {
+                Ok(value) => return Literal::Integer(value, suffix_ty),
+                Err(_) => ParseErrorKind::IntegerTooLarge,
+            }
+        };
+        self.report(ParseError {
+            kind,
+            found: Some(tok.kind),
+            span: tok.span(),
+        });
+
+        Literal::Integer(0, suffix_ty)
+    }
+
+    fn parse_call(
+        &mut self,
+        callee: &'source str,
+        start: Span,
+    ) -> ParseResult<Expression<'source>> {
+        self.expect(TokenKind::LParen)?;
+
+        let mut arguments = Vec::new();
+        while !self.next_is(TokenKind::RParen) {
+            arguments.push(self.parse_expression()?);
+
+            if self.next_is(TokenKind::RParen) {
+                break;
             }
-            TokenKind::Identifier => Expression::Variable(
-                tok.value
-                    .expect("Expected non-empty identifier for variable expression"),
-            ),
-            TokenKind::LParen => self.parse_paren_expression(),
-            _ => panic!("Expected expression, got {:?}", tok),
+
+            self.expect_one_of(&[TokenKind::Comma, TokenKind::RParen])?;
         }
+
+        self.expect(TokenKind::RParen)?;
+
+        Ok(Expression::Call {
+            callee,
+            arguments,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Unary operators bind tighter than any binary operator, so `!a == b`
+    /// is `(!a) == b`.
+    fn parse_unary_operation(
+        &mut self,
+        op: UnaryOperator,
+        start: Span,
+    ) -> ParseResult<Expression<'source>> {
+        let operand = self.parse_expression_primary()?;
+        let span = start.to(operand.span());
+
+        Ok(Expression::UnaryOperation {
+            op,
+            operand: Box::new(operand),
+            span,
+        })
     }
 
-    fn parse_paren_expression(&mut self) -> Expression<'source> {
-        let expr = self.parse_expression();
-        self.expect(TokenKind::RParen);
-        expr
+    fn parse_paren_expression(&mut self) -> ParseResult<Expression<'source>> {
+        let expr = self.parse_expression()?;
+        self.expect(TokenKind::RParen)?;
+        Ok(expr)
     }
 
     fn get_tok_precedence(&mut self) -> u8 {
-        match self.lexer.clone().peekable().peek() {
+        match self.peek() {
             Some(tok) => match tok.kind {
-                TokenKind::OpAdd | TokenKind::OpSub => 20,
-                TokenKind::OpMul | TokenKind::OpDiv | TokenKind::OpRem => 40,
+                TokenKind::OpOr | TokenKind::KeywordOr => 4,
+                TokenKind::OpAnd | TokenKind::KeywordAnd => 6,
+                TokenKind::OpEq
+                | TokenKind::OpNe
+                | TokenKind::OpLt
+                | TokenKind::OpLe
+                | TokenKind::OpGt
+                | TokenKind::OpGe => 10,
+                TokenKind::OpBitOr => 12,
+                TokenKind::OpBitXor => 14,
+                TokenKind::OpBitAnd => 16,
+                TokenKind::OpShl | TokenKind::OpShr => 18,
+                TokenKind::OpAdd
+                | TokenKind::OpSub
+                | TokenKind::OpWrappingAdd
+                | TokenKind::OpWrappingSub
+                | TokenKind::OpSaturatingAdd
+                | TokenKind::OpSaturatingSub => 20,
+                TokenKind::OpMul
+                | TokenKind::OpDiv
+                | TokenKind::OpRem
+                | TokenKind::OpWrappingMul
+                | TokenKind::OpSaturatingMul => 40,
                 _ => 0,
             },
             None => 0,
@@ -253,40 +868,294 @@ impl<'fname, 'source> Parser<'fname, 'source> {
         &mut self,
         lhs: Expression<'source>,
         current_precision: u8,
-    ) -> Expression<'source> {
+    ) -> ParseResult<Expression<'source>> {
         let mut lhs = lhs;
         loop {
             let tok_precision = self.get_tok_precedence();
 
             if tok_precision <= current_precision {
-                return lhs;
+                return Ok(lhs);
             }
 
-            let op = match self.lexer.by_ref().next() {
-                Some(tok) => match tok.kind {
-                    TokenKind::OpAdd => BinaryOperator::Add,
-                    TokenKind::OpSub => BinaryOperator::Sub,
-                    TokenKind::OpMul => BinaryOperator::Mul,
-                    TokenKind::OpDiv => BinaryOperator::Div,
-                    TokenKind::OpRem => BinaryOperator::Rem,
-                    _ => panic!("Expected binary operator, got {:?}", tok),
-                },
-                None => panic!("Expected binary operator, got EOF"),
+            // Only operator tokens have a non-zero precedence.
+            let op = match self.bump().map(|tok| tok.kind) {
+                Some(TokenKind::OpAdd) => BinaryOperator::Add,
+                Some(TokenKind::OpSub) => BinaryOperator::Sub,
+                Some(TokenKind::OpMul) => BinaryOperator::Mul,
+                Some(TokenKind::OpDiv) => BinaryOperator::Div,
+                Some(TokenKind::OpRem) => BinaryOperator::Rem,
+                Some(TokenKind::OpWrappingAdd) => BinaryOperator::WrappingAdd,
+                Some(TokenKind::OpWrappingSub) => BinaryOperator::WrappingSub,
+                Some(TokenKind::OpWrappingMul) => BinaryOperator::WrappingMul,
+                Some(TokenKind::OpSaturatingAdd) => BinaryOperator::SaturatingAdd,
+                Some(TokenKind::OpSaturatingSub) => BinaryOperator::SaturatingSub,
+                Some(TokenKind::OpSaturatingMul) => BinaryOperator::SaturatingMul,
+                Some(TokenKind::OpBitAnd) => BinaryOperator::BitAnd,
+                Some(TokenKind::OpBitOr) => BinaryOperator::BitOr,
+                Some(TokenKind::OpBitXor) => BinaryOperator::BitXor,
+                Some(TokenKind::OpShl) => BinaryOperator::Shl,
+                Some(TokenKind::OpShr) => BinaryOperator::Shr,
+                Some(TokenKind::OpEq) => BinaryOperator::Eq,
+                Some(TokenKind::OpNe) => BinaryOperator::Ne,
+                Some(TokenKind::OpLt) => BinaryOperator::Lt,
+                Some(TokenKind::OpLe) => BinaryOperator::Le,
+                Some(TokenKind::OpGt) => BinaryOperator::Gt,
+                Some(TokenKind::OpGe) => BinaryOperator::Ge,
+                Some(TokenKind::OpAnd | TokenKind::KeywordAnd) => BinaryOperator::And,
+                Some(TokenKind::OpOr | TokenKind::KeywordOr) => BinaryOperator::Or,
+                _ => unreachable!("token with non-zero precedence is not a binary operator"),
             };
 
-            let mut rhs = self.parse_expression_primary();
+            let mut rhs = self.parse_cast_expression()?;
 
             let next_precision = self.get_tok_precedence();
 
             if tok_precision < next_precision {
-                rhs = self.parse_binary_operation(rhs, tok_precision + 1);
+                rhs = self.parse_binary_operation(rhs, tok_precision + 1)?;
             }
 
+            let span = lhs.span().to(rhs.span());
             lhs = Expression::BinaryOperation {
                 lhs: Box::new(lhs),
                 op,
                 rhs: Box::new(rhs),
+                span,
             };
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use zoid_diagnostics::{codes, ErrorCode};
+    use zoid_span::SourceFile;
+
+    use super::*;
+
+    /// Parses `text` as an integer literal, with the span of the error
+    /// reported for it, if any.
+    fn integer(text: &str) -> (Literal<'_>, Option<(usize, usize)>) {
+        let mut parser = Parser::new(FileId(0), "test.zd", text);
+        let tok = Lexer::new(FileId(0), "test.zd", text).next().unwrap();
+        let literal = parser.parse_integer_literal(tok);
+        let span = parser
+            .diagnostics
+            .first()
+            .and_then(Diagnostic::primary_span)
+            .map(|span| (span.start, span.end));
+        (literal, span)
+    }
+
+    #[test]
+    fn integer_literal_radixes() {
+        assert_eq!(integer("0xFF"), (Literal::Integer(255, None), None));
+        assert_eq!(integer("0o755"), (Literal::Integer(0o755, None), None));
+        assert_eq!(integer("0b1010"), (Literal::Integer(10, None), None));
+        assert_eq!(
+            integer("1_000_000"),
+            (Literal::Integer(1_000_000, None), None)
+        );
+    }
+
+    #[test]
+    fn integer_literal_suffixes() {
+        assert_eq!(
+            integer("255u8"),
+            (Literal::Integer(255, Some(Type::U8)), None)
+        );
+        assert_eq!(
+            integer("0x7f_i64"),
+            (Literal::Integer(0x7f, Some(Type::I64)), None)
+        );
+        assert_eq!(
+            integer("1u128"),
+            (Literal::Integer(1, Some(Type::U128)), None)
+        );
+    }
+
+    #[test]
+    fn invalid_integer_literals() {
+        // The error points at the offending part of the literal.
+        assert_eq!(integer("0b102").1, Some((4, 5)));
+        assert_eq!(integer("12u7").1, Some((2, 4)));
+        assert_eq!(integer("0x").1, Some((0, 2)));
+        assert_eq!(
+            integer("340282366920938463463374607431768211456").1,
+            Some((0, 39))
+        );
+    }
+
+    /// Parses `source`, returning the program along with the code, message
+    /// and 1-based line and column of each error.
+    fn parse(source: &str) -> (Program<'_>, Vec<(ErrorCode, String, usize, usize)>) {
+        let (program, diagnostics) = Parser::new(FileId(0), "test.zd", source).parse();
+        let file = SourceFile::new("test.zd", source);
+        let errors = diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                let location = file.lookup(diagnostic.primary_span().unwrap().start);
+                (
+                    diagnostic.code.unwrap(),
+                    diagnostic.message,
+                    location.line,
+                    location.column,
+                )
+            })
+            .collect();
+        (program, errors)
+    }
+
+    /// The span of `needle`, which must occur once in `source`.
+    fn span_of(source: &str, needle: &str) -> Span {
+        assert_eq!(source.matches(needle).count(), 1, "{:?}", needle);
+        let start = source.find(needle).unwrap();
+        Span::new(FileId(0), start, start + needle.len())
+    }
+
+    #[test]
+    fn statement_errors_recover_at_semicolon_or_brace() {
+        let main = "fn main(): i32 {
+    let a = ;
+    let b = 2;
+    return b +;
+    return b;
+}";
+        let f = "fn f() {
+    g()
+}";
+        let source = format!("{}\n{}\nfn h() {{}}\n", main, f);
+        let (program, errors) = parse(&source);
+
+        let expected_expression = "expected expression, found `;`".to_string();
+        assert_eq!(
+            errors,
+            [
+                (
+                    codes::EXPECTED_EXPRESSION,
+                    expected_expression.clone(),
+                    2,
+                    13
+                ),
+                (codes::EXPECTED_EXPRESSION, expected_expression, 4, 15),
+                // A missing `;` is reported at the end of the line.
+                (
+                    codes::EXPECTED_TOKEN,
+                    "expected `;`, found `}`".to_string(),
+                    8,
+                    8
+                ),
+            ]
+        );
+
+        let let_b = span_of(&source, "let b = 2;");
+        let return_b = span_of(&source, "return b;");
+        assert_eq!(
+            program,
+            Program(vec![
+                TopLevelExpression::Function {
+                    name: "main",
+                    parameters: vec![],
+                    return_type: Some(Type::I32),
+                    body: vec![
+                        Statement::VariableDeclaration {
+                            name: "b",
+                            ty: None,
+                            value: Expression::Literal(
+                                Literal::Integer(2, None),
+                                Span::new(FileId(0), let_b.start + 8, let_b.start + 9)
+                            ),
+                            span: let_b,
+                        },
+                        Statement::Return(
+                            Some(Expression::Variable(
+                                "b",
+                                Span::new(FileId(0), return_b.start + 7, return_b.start + 8)
+                            )),
+                            return_b
+                        ),
+                    ],
+                    span: span_of(&source, main),
+                },
+                TopLevelExpression::Function {
+                    name: "f",
+                    parameters: vec![],
+                    return_type: None,
+                    body: vec![],
+                    span: span_of(&source, f),
+                },
+                TopLevelExpression::Function {
+                    name: "h",
+                    parameters: vec![],
+                    return_type: None,
+                    body: vec![],
+                    span: span_of(&source, "fn h() {}"),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn item_errors_recover_at_next_item() {
+        let source = "garbage here
+fn other(x: i32 {
+    return 1;
+}
+const N: i32 = 4;
+fn main() {}
+";
+        let (program, errors) = parse(source);
+
+        assert_eq!(
+            errors,
+            [
+                (
+                    codes::EXPECTED_ITEM,
+                    "expected `fn`, `extern`, `cimport` or `const`, found identifier".to_string(),
+                    1,
+                    1
+                ),
+                (
+                    codes::EXPECTED_TOKEN,
+                    "expected one of `,`, `)`, found `{`".to_string(),
+                    2,
+                    17
+                ),
+            ]
+        );
+        assert_eq!(
+            program,
+            Program(vec![
+                TopLevelExpression::Constant {
+                    name: "N",
+                    ty: Type::I32,
+                    value: Expression::Literal(Literal::Integer(4, None), span_of(source, "4")),
+                    span: span_of(source, "const N: i32 = 4;"),
+                },
+                TopLevelExpression::Function {
+                    name: "main",
+                    parameters: vec![],
+                    return_type: None,
+                    body: vec![],
+                    span: span_of(source, "fn main() {}"),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn unknown_characters_reported_once() {
+        let (program, errors) = parse("fn main() { let a = 1 $ 2; }\n@");
+        assert_eq!(
+            errors
+                .iter()
+                .map(|(code, _, line, column)| (*code, *line, *column))
+                .collect::<Vec<_>>(),
+            [
+                (codes::UNKNOWN_CHARACTER, 1, 23),
+                (codes::EXPECTED_TOKEN, 1, 25),
+                (codes::UNKNOWN_CHARACTER, 2, 1),
+            ]
+        );
+        assert_eq!(program.0.len(), 1);
+    }
+}
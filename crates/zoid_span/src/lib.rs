@@ -0,0 +1,233 @@
+/// Identifies a source file within a compilation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct FileId(pub usize);
+
+/// A byte range `start..end` within a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(file: FileId, start: usize, end: usize) -> Self {
+        Self { file, start, end }
+    }
+
+    /// Returns a span covering both `self` and `other`, which must be in the
+    /// same file.
+    pub fn to(self, other: Span) -> Span {
+        debug_assert_eq!(
+            self.file, other.file,
+            "Cannot join spans from different files"
+        );
+
+        Span {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+    /// Byte offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+/// A position in a source file. Lines and columns are 1-based; editor
+/// protocols such as LSP use 0-based positions, so subtract 1 for those.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub line: usize,
+    /// Column in Unicode scalar values (`char`s), for display to people.
+    pub column: usize,
+    /// Column in UTF-8 bytes.
+    pub utf8_column: usize,
+    /// Column in UTF-16 code units.
+    pub utf16_column: usize,
+}
+
+/// Owns the source text of every file in a compilation and maps byte offsets
+/// within them to lines and columns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceFile {
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Self {
+        let source = source.into();
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self {
+            name: name.into(),
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the 0-based index of the line containing `offset`.
+    pub fn line_index(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        }
+    }
+
+    /// Returns the text of a 1-based line, without its line ending.
+    pub fn line(&self, line: usize) -> &str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+        let text = &self.source[start..end];
+        text.strip_suffix('\r').unwrap_or(text)
+    }
+
+    /// Returns the location of a byte offset. Offsets past the end of the file
+    /// or inside a multi-byte character are moved back to the nearest
+    /// character boundary.
+    pub fn lookup(&self, offset: usize) -> Location {
+        let offset = self.floor_char_boundary(offset);
+        let line = self.line_index(offset);
+        let prefix = &self.source[self.line_starts[line]..offset];
+
+        Location {
+            line: line + 1,
+            column: prefix.chars().count() + 1,
+            utf8_column: prefix.len() + 1,
+            utf16_column: prefix.encode_utf16().count() + 1,
+        }
+    }
+
+    /// Returns the byte offset of a 1-based line and UTF-16 column, as sent by
+    /// editors. Returns `None` if the position is outside the file.
+    pub fn offset_of_utf16(&self, line: usize, utf16_column: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let mut remaining = utf16_column.checked_sub(1)?;
+        for (i, c) in self.line(line).char_indices() {
+            if remaining == 0 {
+                return Some(start + i);
+            }
+            remaining = remaining.checked_sub(c.len_utf16())?;
+        }
+        (remaining == 0).then(|| start + self.line(line).len())
+    }
+
+    fn floor_char_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        self.files.push(SourceFile::new(name, source));
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (FileId, &SourceFile)> {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| (FileId(i), file))
+    }
+
+    pub fn name(&self, id: FileId) -> &str {
+        &self.file(id).name
+    }
+
+    pub fn source(&self, id: FileId) -> &str {
+        &self.file(id).source
+    }
+
+    /// Returns the location of the start of `span`.
+    pub fn lookup(&self, span: Span) -> Location {
+        self.file(span.file).lookup(span.start)
+    }
+
+    /// Returns the locations of the start and end of `span`.
+    pub fn lookup_range(&self, span: Span) -> (Location, Location) {
+        let file = self.file(span.file);
+        (file.lookup(span.start), file.lookup(span.end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_counts_columns_in_each_encoding() {
+        // `é` is 2 bytes in UTF-8, `𝄞` is 4 bytes and a surrogate pair in
+        // UTF-16.
+        let file = SourceFile::new("test.zd", "// é\nlet 𝄞 = x;\n");
+        let x = file.source.find('x').unwrap();
+        assert_eq!(
+            file.lookup(x),
+            Location {
+                line: 2,
+                column: 9,
+                utf8_column: 12,
+                utf16_column: 10,
+            }
+        );
+        assert_eq!(file.offset_of_utf16(2, 10), Some(x));
+    }
+
+    #[test]
+    fn lookup_clamps_to_character_boundaries() {
+        let file = SourceFile::new("test.zd", "é\r\nb");
+        // Inside `é`, and past the end of the file.
+        assert_eq!(file.lookup(1).utf8_column, 1);
+        assert_eq!(file.lookup(100).line, 2);
+        assert_eq!(file.lookup(100).column, 2);
+        assert_eq!(file.line(1), "é");
+        assert_eq!(file.offset_of_utf16(1, 2), Some(2));
+        assert_eq!(file.offset_of_utf16(1, 3), None);
+        assert_eq!(file.offset_of_utf16(3, 1), None);
+    }
+
+    #[test]
+    fn source_map_looks_up_spans_in_their_file() {
+        let mut sources = SourceMap::new();
+        let first = sources.add_file("a.zd", "fn a() {}\n");
+        let second = sources.add_file("b.zd", "\n\n  fn b() {}\n");
+        assert_eq!(sources.name(second), "b.zd");
+        let (start, end) = sources.lookup_range(Span::new(second, 4, 8));
+        assert_eq!((start.line, start.column), (3, 3));
+        assert_eq!((end.line, end.column), (3, 7));
+        assert_eq!(sources.lookup(Span::new(first, 3, 4)).column, 4);
+    }
+}
@@ -1,23 +1,36 @@
-use std::fs;
+use std::{
+    collections::HashSet,
+    fs,
+    io::{stderr, IsTerminal},
+    process::ExitCode,
+};
 
 use clap::Parser as ClapParser;
 
 use zoid_lexer::Lexer;
 
 use options::Options;
+use zoid_ast::TopLevelExpression;
+use zoid_cimport::CImporter;
+use zoid_codegen_llvm::ZoidCodeGenContext;
+use zoid_diagnostics::{codes, Diagnostic, Renderer, Severity};
 use zoid_lowering::ZoidLoweringContext;
 use zoid_parser::Parser;
-use zoid_codegen_llvm::ZoidCodeGenContext;
+use zoid_span::{FileId, SourceMap, Span};
 
 mod options;
 
-fn main() {
+fn main() -> ExitCode {
     let opts = Options::parse();
 
     println!("Input file: {}", opts.input.display());
     let file_name = opts.input.file_name().expect("Cannot get file name");
 
     let source = fs::read_to_string(&opts.input).expect("Unable to read file");
+    let file_name = file_name.to_string_lossy().into_owned();
+
+    let mut source_map = SourceMap::new();
+    let file_id = source_map.add_file(&file_name, source.clone());
 
     eprintln!("Source Code:");
     for (i, line) in source.lines().enumerate() {
@@ -25,26 +38,81 @@ fn main() {
     }
     eprintln!();
 
-    let mut lexer = Lexer::new(file_name.to_str().unwrap(), &source);
+    let mut lexer = Lexer::new(file_id, &file_name, &source);
     eprintln!("Tokens:");
     while let Some(tok) = lexer.next_token() {
         eprintln!("\t{}", tok);
     }
     eprintln!();
 
-    let mut parser = Parser::new(file_name.to_str().unwrap(), &source);
+    let mut parser = Parser::new(file_id, &file_name, &source);
     eprintln!("AST:");
-    let program = parser.parse();
+    let (mut program, mut diagnostics) = parser.parse();
     eprintln!("{:#?}", program);
     eprintln!();
 
+    // Each imported header becomes a file of its own. The program borrows
+    // `source` rather than the source map, so files can still be added.
+    let headers: Vec<_> = program
+        .0
+        .iter()
+        .filter_map(|item| match item {
+            TopLevelExpression::CImport { header, span } => Some((*header, *span)),
+            _ => None,
+        })
+        .collect();
+    let mut importer = CImporter::new();
+    if let Some(dir) = opts.input.parent() {
+        importer = importer.with_include_dir(dir);
+    }
+    let (imported_files, import_diagnostics) =
+        import_headers(&mut importer, &mut source_map, &headers);
+
+    let mut reporter = Reporter {
+        renderer: Renderer::new(&source_map).with_color(stderr().is_terminal()),
+        errors: 0,
+    };
+
+    // Headers share declarations, such as `ctermid` in both `stdio.h` and
+    // `unistd.h`, so a name is only imported from the first header declaring
+    // it. C allows compatible redeclarations, so the first is as good as any.
+    let mut imported_names = HashSet::new();
+    for imported_file in imported_files {
+        let mut parser = Parser::new(
+            imported_file,
+            source_map.name(imported_file),
+            source_map.source(imported_file),
+        );
+        let (imported, imported_diagnostics) = parser.parse();
+        program.0.extend(
+            imported
+                .0
+                .into_iter()
+                .filter(|item| item.name().is_none_or(|name| imported_names.insert(name))),
+        );
+        diagnostics.extend(imported_diagnostics);
+    }
+
+    reporter.emit(&import_diagnostics);
+    reporter.emit(&diagnostics);
+    if reporter.errors > 0 {
+        return reporter.abort(&file_name);
+    }
+
     let mut lowering = ZoidLoweringContext::new(program);
     eprintln!("HLIR:");
     let hlir = lowering.lower();
     // eprintln!("{:#?}", lowering);
     eprintln!("{:#?}", hlir);
 
-    let mut codegen = ZoidCodeGenContext::new(hlir);
+    reporter.emit(&lowering.diagnostics);
+    if reporter.errors > 0 {
+        return reporter.abort(&file_name);
+    }
+
+    let mut codegen = ZoidCodeGenContext::new(hlir)
+        .with_source_map(&source_map)
+        .with_overflow_checks(opts.overflow_checks.unwrap_or(!opts.release));
     codegen.codegen();
     codegen.verify();
 
@@ -57,4 +125,91 @@ fn main() {
     eprintln!("Optimized LLVM IR:");
     codegen.dump();
     eprintln!();
+
+    reporter.emit(&codegen.diagnostics);
+    if reporter.errors > 0 {
+        return reporter.abort(&file_name);
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Imports the C headers named by `cimport "header.h";` items, adding the
+/// Zoid declarations for each to `source_map`. Returns the files added, along
+/// with errors for headers that could not be imported and warnings for
+/// declarations that were skipped.
+fn import_headers(
+    importer: &mut CImporter,
+    source_map: &mut SourceMap,
+    headers: &[(&str, Span)],
+) -> (Vec<FileId>, Vec<Diagnostic>) {
+    let mut files = Vec::new();
+    let mut diagnostics = Vec::new();
+    for &(header, span) in headers {
+        let import = match importer.import(header) {
+            Ok(import) => import,
+            Err(err) => {
+                diagnostics.push(
+                    Diagnostic::error(format!("could not import `{}`", header))
+                        .with_code(codes::CIMPORT_FAILED)
+                        .with_primary(span, "imported here")
+                        .with_note(err.to_string()),
+                );
+                continue;
+            }
+        };
+
+        if !import.skipped.is_empty() {
+            let count = import.skipped.len();
+            let mut diagnostic = Diagnostic::warning(format!(
+                "{} declaration{} in `{}` could not be imported",
+                count,
+                if count == 1 { "" } else { "s" },
+                header
+            ))
+            .with_primary(span, "imported here");
+            for skipped in &import.skipped {
+                diagnostic = diagnostic.with_note(match &skipped.name {
+                    Some(name) => format!("`{}` {}", name, skipped.reason),
+                    None => format!("a declaration {}", skipped.reason),
+                });
+            }
+            diagnostics.push(diagnostic);
+        }
+
+        files.push(source_map.add_file(format!("<{}>", header), import.source.clone()));
+    }
+
+    (files, diagnostics)
+}
+
+/// Prints diagnostics to stderr and keeps count of the errors.
+struct Reporter<'a> {
+    renderer: Renderer<'a>,
+    errors: usize,
+}
+
+impl Reporter<'_> {
+    fn emit(&mut self, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            if diagnostic.is_error() {
+                self.errors += 1;
+            }
+            eprintln!("{}", self.renderer.render(diagnostic));
+        }
+    }
+
+    fn abort(&self, file_name: &str) -> ExitCode {
+        let summary = Diagnostic::new(
+            Severity::Error,
+            format!(
+                "could not compile `{}` due to {} previous error{}",
+                file_name,
+                self.errors,
+                if self.errors == 1 { "" } else { "s" }
+            ),
+        );
+        eprintln!("{}", self.renderer.render(&summary));
+        ExitCode::FAILURE
+    }
 }
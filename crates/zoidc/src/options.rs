@@ -7,5 +7,13 @@ use clap::Parser;
 /// The Zoid language compiler
 pub struct Options {
     /// Input file for the Zoid language compiler
-    pub input: PathBuf
+    pub input: PathBuf,
+    /// Compile for release, which turns off overflow checks unless
+    /// `--overflow-checks` says otherwise
+    #[clap(long)]
+    pub release: bool,
+    /// Whether arithmetic that overflows traps at run time instead of
+    /// wrapping around
+    #[clap(long, value_name = "BOOL")]
+    pub overflow_checks: Option<bool>,
 }